@@ -2,12 +2,15 @@ mod util;
 
 use std::{time::Duration, sync::{Arc, Mutex}};
 
-use cgmath::{Vector3, Zero};
+use cgmath::{Vector3, Zero, InnerSpace};
 use util::{
     terrain, 
     controller, 
     tile, 
-    entity
+    entity, 
+    chaser, 
+    swarm, 
+    guard
 };
 
 use block_engine_wgpu::{
@@ -15,12 +18,92 @@ use block_engine_wgpu::{
     Config,
     camera, 
     world, 
+    texture,
     GameData, 
     GameEvent, GameWindow  
 };
 
+const PLAYER_SPAWN: (f32, f32, f32) = (0.0, 6.0, 0.0);
+const PLAYER_LIGHT: [f32; 4] = [1.0, 0.4, 0.1, 0.4];
+const PLATE_LIGHT: [f32; 4] = [0.2, 0.9, 0.9, 0.6];
+const SKYLIGHT: [f32; 3] = [0.5, 0.6, 0.8];
+
+const CHASER_SPAWN: (f32, f32, f32) = (-7.0, 2.0, 0.0);
+const CHASER_SPEED: f32 = 0.15;
+
+const SWARM_CENTER: (f32, f32, f32) = (5.0, 2.0, 5.0);
+const SWARM_SIZE: usize = 6;
+
+const GUARD_POSTS: [(f32, f32, f32); 2] = [(-7.0, 0.9, 6.0), (7.0, 0.9, 5.0)];
+
+const BARRIER_X: std::ops::RangeInclusive<i16> = -8..=6;
+const BARRIER_Z: i16 = -1;
+
+const EXPLOSION_RADIUS: f32 = 2.0;
+const EXPLOSION_STRENGTH: f32 = 1.5;
+
+const TRAIL: world::ParticleConfig = world::ParticleConfig {
+    rate: 0.5,
+    direction: Vector3::new(0.0, 1.0, 0.0),
+    spread: std::f32::consts::PI,
+    speed: 0.01,
+    speed_variance: 0.5,
+    gravity_scale: 0.0,
+    drag: 0.05,
+    lifetime: 20,
+    start_color: [1.0, 1.0, 0.8],
+    end_color: [0.4, 0.4, 0.4],
+    start_size: 0.08,
+    end_size: 0.0,
+    collisions: None,
+    light: None
+};
+
+const SPARKS: world::ParticleConfig = world::ParticleConfig {
+    rate: 0.0,
+    direction: Vector3::new(0.0, 1.0, 0.0),
+    spread: std::f32::consts::PI,
+    speed: 0.15,
+    speed_variance: 0.6,
+    gravity_scale: 0.5,
+    drag: 0.04,
+    lifetime: 40,
+    start_color: [1.0, 0.8, 0.2],
+    end_color: [0.8, 0.1, 0.0],
+    start_size: 0.12,
+    end_size: 0.02,
+    collisions: Some(0.4),
+    light: Some([1.0, 0.6, 0.1, 0.8])
+};
+const SPARK_COUNT: usize = 40;
+
+// Packs the textures that the terrain is drawn with
+fn build_texture_atlas() -> Result<texture::TextureAtlas, texture::TextureError> {
+    let mut builder = texture::TextureAtlasBuilder::new();
+    builder
+        .add_png("grass_top", include_bytes!("res/grass_top.png"))?
+        .add_png("grass_side", include_bytes!("res/grass_side.png"))?
+        .add_png("dirt", include_bytes!("res/dirt.png"))?
+        .add_png_with_normal_map(
+            "stone",
+            include_bytes!("res/stone.png"),
+            include_bytes!("res/stone_normal.png")
+        )?;
+
+    builder.build()
+}
+
 fn game_init(data: GameData) {
+    // The terrain is left untextured if the atlas can't be built
+    match build_texture_atlas() {
+        Ok(atlas) => data.world.set_texture_atlas(atlas),
+        Err(error) => eprintln!("{}", error)
+    }
+
     terrain::generate(data.world);
+    data.world.set_sun(Some(world::Sun::default()));
+    data.world.set_point_shadow_count(4);
+    data.world.set_ambient_occlusion(true);
 
     data.world.add_tile( {
         let mut pl = tile::Cube::new(
@@ -32,16 +115,79 @@ fn game_init(data: GameData) {
     data.world.add_entity_with_tag(
         "player",
         entity::PlaceholderEntity {
-            center: (0.0, 6.0, 0.0).into(),
+            center: PLAYER_SPAWN.into(),
             color: [1.0; 3],
-            light: Some([1.0, 0.4, 0.1, 0.4]),
+            light: Some(PLAYER_LIGHT),
             velocity: (0.0, 0.0, 0.0).into(),
             collisions: (false, false, false).into(),
-            weight: 0.2,
+            mass: 1.0,
+            linear_drag: 0.05,
+            gravity_scale: 1.0,
+            collider: world::Collider::Aabb { half_extents: (0.3, 0.4, 0.3).into() },
+            collision_mode: world::CollisionMode::Solid,
         },
         None
     );
 
+    // Follows the player around the arena
+    data.world.add_entity_with_tag(
+        "chaser",
+        entity::PlaceholderEntity {
+            center: CHASER_SPAWN.into(),
+            color: [1.0, 0.2, 0.2],
+            light: Some([1.0, 0.1, 0.1, 0.3]),
+            velocity: (0.0, 0.0, 0.0).into(),
+            collisions: (false, false, false).into(),
+            mass: 1.0,
+            linear_drag: 0.05,
+            gravity_scale: 1.0,
+            collider: world::Collider::Capsule { radius: 0.3, half_height: 0.1 },
+            collision_mode: world::CollisionMode::Solid,
+        },
+        None
+    );
+
+    // Patrols the far side of the arena, and gives chase when it spots the player
+    data.world.add_entity_with_tag(
+        "guard",
+        entity::PlaceholderEntity {
+            center: GUARD_POSTS[0].into(),
+            color: [1.0, 0.8, 0.2],
+            light: Some([1.0, 0.8, 0.2, 0.3]),
+            velocity: (0.0, 0.0, 0.0).into(),
+            collisions: (false, false, false).into(),
+            mass: 1.0,
+            linear_drag: 0.05,
+            gravity_scale: 1.0,
+            collider: world::Collider::Aabb { half_extents: (0.3, 0.4, 0.3).into() },
+            collision_mode: world::CollisionMode::Solid,
+        },
+        None
+    );
+
+    data.world.set_entity_collisions(true);
+    data.world.set_physics_config(world::PhysicsConfig {
+        substeps: 2,
+        ..Default::default()
+    } );
+    let crate_mesh = data.world.add_mesh(entity::Crate::build_mesh());
+    for center in [(3.0, 2.0, 3.0), (-4.0, 2.0, 2.0), (2.0, 2.0, -5.0)] {
+        data.world.add_entity(entity::Crate::new(center.into(), [0.6, 0.4, 0.2], crate_mesh), None);
+    }
+
+    // Anything that falls through the pit ends up here
+    data.world.add_trigger_volume(
+        "kill_zone", 
+        (-20.0, -20.0, -20.0).into(), 
+        (20.0, -5.0, 20.0).into()
+    );
+
+    // Walking through the plate changes the color of the player's light
+    data.world.add_tile(
+        tile::Cube::new((-3, 1, 3).into(), [0.2, 0.9, 0.9])
+            .with_collision_mode(world::CollisionMode::Trigger)
+    );
+
     *data.camera = camera::CameraBuilder::new()
         .pitch(1.0)
         .yaw(0.1)
@@ -54,39 +200,132 @@ fn main() {
 
     let controller = Arc::new(Mutex::new(controller::PlayerController {
         direction: 0,
-        acceleration: 0.15,
+        jump: false,
         pressed: false,
         current_drag_vector: Vector3::zero(),
     } ));
 
     let game_update = {
         let controller_ref = Arc::clone(&controller);
+        let mut character = world::CharacterController::default();
+        let mut chaser = chaser::Chaser::new(world::CharacterConfig {
+            max_speed: CHASER_SPEED,
+            ..Default::default()
+        } );
+        let mut projectiles: Vec<world::EntityHandle> = Vec::new();
+        let mut swarm: Option<swarm::Swarm> = None;
+        let mut guard = guard::build(GUARD_POSTS[0].into(), GUARD_POSTS[1].into());
 
         move |data: GameData| {
+            // Projectiles explode when they strike a wall or another entity
+            for event in data.world.drain_collision_events() {
+                let is_projectile = projectiles.iter().any(|p| p.ptr_eq(&event.entity));
+                let is_impact = match event.target {
+                    world::CollisionTarget::Tile(..) => event.normal.y.abs() < 0.5,
+                    world::CollisionTarget::Entity(..) => true,
+                };
+
+                if is_projectile && is_impact && data.world.remove_entity(&event.entity) {
+                    let origin = event.entity.borrow().center();
+
+                    data.world.add_emitter(
+                        world::ParticleEmitter::new(origin, SPARKS)
+                            .with_burst(SPARK_COUNT)
+                            .with_duration(0)
+                    );
+
+                    for nearby in data.world.entities_in_radius(origin, EXPLOSION_RADIUS) {
+                        let push = nearby.borrow().center() - origin;
+                        if push.magnitude2() > 0.0 {
+                            let falloff = 1.0 - (push.magnitude() / EXPLOSION_RADIUS).min(1.0);
+                            data.world.apply_impulse(&nearby, push.normalize() * EXPLOSION_STRENGTH * falloff);
+                        }
+                    }
+                }
+            }
+
+            let player = data.world.get_entity("player").unwrap();
+            for event in data.world.drain_trigger_events() {
+                let is_player = event.entity.ptr_eq(&player);
+
+                match (event.source, event.phase) {
+                    (world::TriggerSource::Volume("kill_zone"), world::TriggerPhase::Enter) => {
+                        if is_player {
+                            let mut entity = event.entity.clone();
+                            let mut entity = entity.borrow_mut();
+                            entity.set_center(PLAYER_SPAWN.into());
+                            entity.set_velocity(Vector3::zero());
+                        } else {
+                            data.world.remove_entity(&event.entity);
+                        }
+                    },
+                    (world::TriggerSource::Tile(..), world::TriggerPhase::Enter) if is_player => {
+                        event.entity.clone().borrow_mut().set_light(PLATE_LIGHT);
+                    },
+                    (world::TriggerSource::Tile(..), world::TriggerPhase::Exit) if is_player => {
+                        event.entity.clone().borrow_mut().set_light(PLAYER_LIGHT);
+                    },
+                    _ => {  }
+                }
+            }
+
+            projectiles.retain(|p| data.world.contains_entity_handle(p));
+
             let center = data.world.get_entity("player").unwrap().borrow().center();
             data.camera.set_target((center.x, center.y.round(), center.z).into());
 
-            let mut handle = data.world.get_entity("player").unwrap();
-            let mut entity = handle.borrow_mut();
+            {
+                let mut controller = controller_ref.lock().unwrap();
+
+                character.set_movement(controller.movement());
+                if controller.take_jump() {
+                    character.jump();
+                }
+            }
+
+            character.update(data.world, &player);
+
+            if let Some(handle) = data.world.get_entity("chaser") {
+                chaser.update(data.world, &handle, player.borrow().center());
+            }
+
+            if let Some(handle) = data.world.get_entity("guard") {
+                guard.tick(data.world, &handle);
+            }
+
+            swarm
+                .get_or_insert_with(|| swarm::Swarm::spawn(data.world, SWARM_CENTER.into(), SWARM_SIZE))
+                .update(data.world, &player);
 
             {
-                let mut velocity = entity.velocity();
-                controller_ref.lock().unwrap().aggregate_player_velocity(&mut velocity);
-                entity.set_velocity(velocity);
+                let entity = player.borrow();
 
                 if let Some(mut drag_vector) = controller_ref.lock().unwrap().spawn_projectile() {
                     drag_vector *= -1.0;
 
+                    // Spawn the projectile just outside of the player's collider
+                    let offset = drag_vector.normalize() * 
+                        (entity.collider().half_extents().x * 2.0_f32.sqrt() + 0.15);
+
                     let entity = entity::PlaceholderEntity {
-                        center: entity.center(),
+                        center: entity.center() + offset,
                         color: [1.0; 3],
                         light: Some([1.0, 1.0, 1.0, 0.2]),
                         velocity: drag_vector,
                         collisions: (false, false, false).into(),
-                        weight: 0.05,
+                        mass: 0.2,
+                        linear_drag: 0.01,
+                        gravity_scale: 0.5,
+                        collider: world::Collider::Sphere { radius: 0.1 },
+                        collision_mode: world::CollisionMode::Solid,
                     };
 
-                    data.world.add_entity(entity, Some(Duration::from_secs(4)));
+                    let handle = data.world.add_entity(entity, Some(Duration::from_secs(4)));
+                    data.world.add_emitter(
+                        world::ParticleEmitter::new(handle.borrow().center(), TRAIL).attached_to(&handle)
+                    );
+
+                    projectiles.push(handle);
                 }
             }
         }
@@ -95,6 +334,73 @@ fn main() {
     let process_events = {
         let controller_ref = Arc::clone(&controller);
         move |window: GameWindow, event: GameEvent, data: GameData| {
+            // Toggle the collider outlines
+            if let GameEvent::Key { 
+                code: winit::event::VirtualKeyCode::F1, 
+                state: winit::event::ElementState::Pressed 
+            } = event {
+                let enabled = data.world.debug_colliders();
+                data.world.set_debug_colliders(!enabled);
+            }
+
+            // Raise or lower a barrier across the arena, which the chaser has to find a way around
+            if let GameEvent::Key { 
+                code: winit::event::VirtualKeyCode::B, 
+                state: winit::event::ElementState::Pressed 
+            } = event {
+                let raised = data.world.contains_tile(&(*BARRIER_X.start(), 1, BARRIER_Z).into());
+
+                for x in BARRIER_X {
+                    let position = (x, 1, BARRIER_Z).into();
+                    if raised {
+                        data.world.remove_tile(position);
+                    } else {
+                        data.world.add_tile(tile::Cube::new(position, [0.5; 3]));
+                    }
+                }
+            }
+
+            // Cycle between normal, low and zero gravity
+            if let GameEvent::Key { 
+                code: winit::event::VirtualKeyCode::G, 
+                state: winit::event::ElementState::Pressed 
+            } = event {
+                let mut physics_config = data.world.physics_config();
+                let default_gravity = world::PhysicsConfig::default().gravity;
+
+                physics_config.gravity = if physics_config.gravity == default_gravity {
+                    default_gravity * 0.25
+                } else if physics_config.gravity.is_zero() {
+                    default_gravity
+                } else {
+                    Vector3::zero()
+                };
+
+                data.world.set_physics_config(physics_config);
+            }
+
+            // Switch between day and night
+            if let GameEvent::Key { 
+                code: winit::event::VirtualKeyCode::N, 
+                state: winit::event::ElementState::Pressed 
+            } = event {
+                let sun = data.world.sun();
+                data.world.set_sun(if sun.is_some() { None } else { Some(world::Sun::default()) } );
+            }
+
+            // Switch between point lights and voxel lighting
+            if let GameEvent::Key { 
+                code: winit::event::VirtualKeyCode::V, 
+                state: winit::event::ElementState::Pressed 
+            } = event {
+                let lighting = data.world.voxel_lighting();
+                data.world.set_voxel_lighting(if lighting.is_some() { 
+                    None 
+                } else { 
+                    Some(world::VoxelLighting { skylight: Some(SKYLIGHT) } ) 
+                } );
+            }
+
             controller_ref.lock().unwrap().process_events(window, event, data.camera);
     
             false
@@ -0,0 +1,77 @@
+use block_engine_wgpu::{
+    navigation, 
+    world
+};
+
+use cgmath::{
+    Point3, 
+    Vector3, 
+    InnerSpace, 
+    Zero
+};
+
+// Ticks between searches for a new path to the target
+const REPATH_TICKS: u32 = 30;
+
+// Horizontal distance at which a waypoint counts as reached
+const WAYPOINT_RADIUS: f32 = 0.25;
+
+// Follows a target around the world along paths found on the navigation graph
+pub struct Chaser {
+    character: world::CharacterController,
+    graph: Option<navigation::NavigationGraph>,
+    path: Vec<Point3<f32>>,
+    ticks_until_repath: u32
+}
+
+impl Chaser {
+    pub fn new(character_config: world::CharacterConfig) -> Self {
+        Self {
+            character: world::CharacterController::new(character_config),
+            graph: None,
+            path: Vec::new(),
+            ticks_until_repath: 0
+        }
+    }
+
+    pub fn update(&mut self, world: &mut world::World, handle: &world::EntityHandle, target: Point3<f32>) {
+        let center = handle.borrow().center();
+
+        if self.ticks_until_repath == 0 {
+            // The graph is built the first time it is needed, and kept up to date with the world after that
+            let graph = self.graph.get_or_insert_with(|| {
+                navigation::NavigationGraph::new(world, Default::default())
+            } );
+
+            // The first waypoint is where the chaser is standing
+            self.path = graph
+                .find_path(world, center, target)
+                .map(|path| path.into_iter().skip(1).collect())
+                .unwrap_or_default();
+
+            self.ticks_until_repath = REPATH_TICKS;
+        } else {
+            self.ticks_until_repath -= 1;
+        }
+
+        while let Some(waypoint) = self.path.first() {
+            let offset = waypoint - center;
+            if Vector3::new(offset.x, 0.0, offset.z).magnitude() < WAYPOINT_RADIUS && offset.y.abs() < 0.6 {
+                self.path.remove(0);
+            } else {
+                break;
+            }
+        }
+
+        let movement = match self.path.first() {
+            Some(waypoint) => {
+                let offset = waypoint - center;
+                Vector3::new(offset.x, 0.0, offset.z).normalize()
+            },
+            None => Vector3::zero()
+        };
+
+        self.character.set_movement(movement);
+        self.character.update(world, handle);
+    }
+}
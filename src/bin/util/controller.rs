@@ -31,7 +31,7 @@ pub mod directions {
 
 pub struct PlayerController {
     pub direction: u8,
-    pub acceleration: f32,
+    pub jump: bool,
     pub pressed: bool,
     pub current_drag_vector: Vector3<f32>,
 }
@@ -46,7 +46,7 @@ impl PlayerController {
         match &event {    
             // Zoom
             GameEvent::MouseWheel { delta } => {
-                let scroll_amount = -1.0 * match delta {
+                let scroll_amount = -match delta {
                     // A mouse line is about 1 px.
                     event::MouseScrollDelta::LineDelta(_, scroll) => 
                         scroll * 1.0,
@@ -78,6 +78,11 @@ impl PlayerController {
                 self.current_drag_vector = Vector3::new(degree.0 as f32, 0.0, degree.1 as f32)
             }
     
+            GameEvent::Key { 
+                code: event::VirtualKeyCode::Space, 
+                state: event::ElementState::Pressed 
+            } => self.jump = true,
+
             // Player pressed movement keys
             GameEvent::Key { 
                 code: event::VirtualKeyCode::Left, 
@@ -134,9 +139,17 @@ impl PlayerController {
         None
     }
 
-    pub fn aggregate_player_velocity(&mut self, velocity: &mut Vector3<f32>) {
-        velocity.x += self.acceleration * directions::x_signum(self.direction);
-        velocity.z += self.acceleration * directions::z_signum(self.direction);
+    pub fn movement(&self) -> Vector3<f32> {
+        Vector3::new(
+            directions::x_signum(self.direction), 
+            0.0, 
+            directions::z_signum(self.direction)
+        )
+    }
+
+    // Jumps are only reported once per key press
+    pub fn take_jump(&mut self) -> bool {
+        std::mem::take(&mut self.jump)
     }
 }
 
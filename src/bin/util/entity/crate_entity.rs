@@ -0,0 +1,93 @@
+use block_engine_wgpu::world;
+
+use cgmath::{
+    Point3, 
+    Vector3, 
+    Matrix4, 
+    EuclideanSpace
+};
+
+use crate::util::mesh;
+
+// A box that can be pushed around by other entities
+pub struct Crate {
+    pub center: Point3<f32>,
+    pub hw: f32,
+    pub color: [f32; 3],
+    pub mesh: world::MeshId,
+
+    pub velocity: Vector3<f32>,
+    pub collisions: Vector3<bool>,
+    pub mass: f32,
+    pub linear_drag: f32,
+    pub gravity_scale: f32,
+    pub collision_mode: world::CollisionMode
+}
+
+impl Crate {
+    // Every crate is drawn with the same unit box, which is scaled to fit
+    pub fn build_mesh() -> world::Triangles {
+        mesh::build_box((-0.5, -0.5, -0.5).into(), (0.5, 0.5, 0.5).into(), [1.0; 3])
+    }
+
+    pub fn new(center: Point3<f32>, color: [f32; 3], mesh: world::MeshId) -> Self {
+        Self {
+            center,
+            hw: 0.4,
+            color,
+            mesh,
+            velocity: (0.0, 0.0, 0.0).into(),
+            collisions: (false, false, false).into(),
+            mass: 3.0,
+            linear_drag: 0.05,
+            gravity_scale: 1.0,
+            collision_mode: world::CollisionMode::Solid
+        }
+    }
+}
+
+impl world::Entity for Crate {
+    fn velocity(&self) -> Vector3<f32> { self.velocity }
+    fn set_velocity(&mut self, velocity: Vector3<f32>) { self.velocity = velocity; }
+
+    fn mass(&self) -> f32 { self.mass }
+    fn set_mass(&mut self, mass: f32) { self.mass = mass; }
+
+    fn linear_drag(&self) -> f32 { self.linear_drag }
+    fn set_linear_drag(&mut self, linear_drag: f32) { self.linear_drag = linear_drag; }
+
+    fn gravity_scale(&self) -> f32 { self.gravity_scale }
+    fn set_gravity_scale(&mut self, gravity_scale: f32) { self.gravity_scale = gravity_scale; }
+
+    // The collider always matches the box's geometry
+    fn collider(&self) -> world::Collider {
+        world::Collider::Aabb { half_extents: (self.hw, self.hw, self.hw).into() }
+    }
+
+    fn set_collider(&mut self, collider: world::Collider) {
+        self.hw = collider.half_extents().x;
+    }
+
+    fn collision_mode(&self) -> world::CollisionMode { self.collision_mode }
+    fn set_collision_mode(&mut self, collision_mode: world::CollisionMode) { self.collision_mode = collision_mode; }
+
+    fn collisions(&self) -> Vector3<bool> { self.collisions }
+    fn set_collisions(&mut self, collisions: Vector3<bool>) { self.collisions = collisions; }
+
+    fn mesh(&self) -> Option<world::MeshId> { Some(self.mesh) }
+
+    fn transform(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.center.to_vec()) * Matrix4::from_scale(self.hw * 2.0)
+    }
+}
+
+impl world::Drawable for Crate {
+    fn center(&self) -> Point3<f32> { self.center }
+    fn set_center(&mut self, center: Point3<f32>) { self.center = center; }
+
+    fn color(&self) -> [f32; 3] { self.color }
+    fn set_color(&mut self, color: [f32; 3]) { self.color = color; }
+
+    fn light(&self) -> Option<[f32; 4]> { None }
+    fn set_light(&mut self, _light: [f32; 4]) {  }
+}
@@ -1,2 +1,5 @@
 mod placeholder_entity;
-pub use placeholder_entity::PlaceholderEntity;
\ No newline at end of file
+pub use placeholder_entity::PlaceholderEntity;
+
+mod crate_entity;
+pub use crate_entity::Crate;
@@ -12,7 +12,11 @@ pub struct PlaceholderEntity {
 
     pub velocity: Vector3<f32>,
     pub collisions: Vector3<bool>,
-    pub weight: f32
+    pub mass: f32,
+    pub linear_drag: f32,
+    pub gravity_scale: f32,
+    pub collider: world::Collider,
+    pub collision_mode: world::CollisionMode
 }
 
 impl world::Entity for PlaceholderEntity {
@@ -24,12 +28,44 @@ impl world::Entity for PlaceholderEntity {
         self.velocity = velocity;
     }
 
-    fn weight(&self) -> f32 {
-        self.weight
+    fn mass(&self) -> f32 {
+        self.mass
     }
 
-    fn set_weight(&mut self, weight: f32) {
-        self.weight = weight;
+    fn set_mass(&mut self, mass: f32) {
+        self.mass = mass;
+    }
+
+    fn linear_drag(&self) -> f32 {
+        self.linear_drag
+    }
+
+    fn set_linear_drag(&mut self, linear_drag: f32) {
+        self.linear_drag = linear_drag;
+    }
+
+    fn gravity_scale(&self) -> f32 {
+        self.gravity_scale
+    }
+
+    fn set_gravity_scale(&mut self, gravity_scale: f32) {
+        self.gravity_scale = gravity_scale;
+    }
+
+    fn collider(&self) -> world::Collider {
+        self.collider
+    }
+
+    fn set_collider(&mut self, collider: world::Collider) {
+        self.collider = collider;
+    }
+
+    fn collision_mode(&self) -> world::CollisionMode {
+        self.collision_mode
+    }
+
+    fn set_collision_mode(&mut self, collision_mode: world::CollisionMode) {
+        self.collision_mode = collision_mode;
     }
 
     fn collisions(&self) -> Vector3<bool> {
@@ -39,6 +75,11 @@ impl world::Entity for PlaceholderEntity {
     fn set_collisions(&mut self, collisions: Vector3<bool>) {
         self.collisions = collisions;
     }
+
+    // Placeholders are only seen through the light they give off
+    fn mesh(&self) -> Option<world::MeshId> {
+        None
+    }
 }
 
 impl world::Drawable for PlaceholderEntity {
@@ -65,11 +106,4 @@ impl world::Drawable for PlaceholderEntity {
     fn set_light(&mut self, light: [f32; 4]) {
         self.light = Some(light);
     }
-
-    fn build_object_data(&self) -> world::Triangles {
-        world::Triangles {
-            vertices: Vec::new(),
-            indices: Vec::new(),
-        }
-    }
 }
\ No newline at end of file
@@ -0,0 +1,32 @@
+use block_engine_wgpu::behaviour;
+
+use cgmath::Point3;
+
+// Builds the guard's behaviour tree, which patrols between the two posts
+pub fn build(post_a: Point3<f32>, post_b: Point3<f32>) -> behaviour::BehaviourTree {
+    let mut parser = behaviour::TreeParser::default();
+
+    // Clears a value from the blackboard
+    parser.register("forget", |arguments| {
+        let key = match arguments {
+            [key] => key.as_text()?.to_string(),
+            _ => return Err(behaviour::ParseError { 
+                message: "'forget' takes a key".to_string(), 
+                position: 0 
+            } )
+        };
+
+        Ok(Box::new(behaviour::Action::new(move |context: &mut behaviour::Context| {
+            context.blackboard.remove(&key);
+            behaviour::Status::Success
+        } )))
+    } );
+
+    let mut tree = parser
+        .parse(include_str!("../res/guard.tree"))
+        .unwrap_or_else(|error| panic!("Invalid guard behaviour: {}", error));
+
+    tree.blackboard.insert("post_a", post_a);
+    tree.blackboard.insert("post_b", post_b);
+    tree
+}
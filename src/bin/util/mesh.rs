@@ -0,0 +1,78 @@
+use block_engine_wgpu::{world, Vertex};
+
+use cgmath::{
+    Point3, 
+    Vector3
+};
+
+// Corners must wind counter-clockwise when seen from the side the normal faces
+pub fn push_quad(
+    triangles: &mut world::Triangles, 
+    corners: [Point3<f32>; 4], 
+    normal: Vector3<f32>, 
+    color: [f32; 3]
+) {
+    let offset = triangles.vertices.len() as u32;
+    for corner in corners {
+        triangles.vertices.push(Vertex::new(corner.into(), color, normal.into()));
+    }
+
+    triangles.indices.extend_from_slice(&[
+        offset, offset + 1, offset + 2, 
+        offset, offset + 2, offset + 3
+    ]);
+}
+
+pub fn push_triangle(
+    triangles: &mut world::Triangles, 
+    corners: [Point3<f32>; 3], 
+    normal: Vector3<f32>, 
+    color: [f32; 3]
+) {
+    let offset = triangles.vertices.len() as u32;
+    for corner in corners {
+        triangles.vertices.push(Vertex::new(corner.into(), color, normal.into()));
+    }
+
+    triangles.indices.extend_from_slice(&[offset, offset + 1, offset + 2]);
+}
+
+pub fn build_box(min: Point3<f32>, max: Point3<f32>, color: [f32; 3]) -> world::Triangles {
+    let faces: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+        // normal, first edge, second edge
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0])
+    ];
+
+    let center = Point3::new((min.x + max.x) * 0.5, (min.y + max.y) * 0.5, (min.z + max.z) * 0.5);
+    let half_extents = (max - min) * 0.5;
+    let scale = |v: Vector3<f32>| Vector3::new(
+        v.x * half_extents.x, 
+        v.y * half_extents.y, 
+        v.z * half_extents.z
+    );
+
+    let mut triangles = world::Triangles { 
+        vertices: Vec::with_capacity(24), 
+        indices: Vec::with_capacity(36) 
+    };
+
+    for (normal, u, v) in faces {
+        let (normal, u, v): (Vector3<f32>, Vector3<f32>, Vector3<f32>) = 
+            (normal.into(), u.into(), v.into());
+
+        let corner = |su: f32, sv: f32| center + scale(normal + u * su + v * sv);
+        push_quad(
+            &mut triangles, 
+            [corner(-1.0, -1.0), corner(1.0, -1.0), corner(1.0, 1.0), corner(-1.0, 1.0)], 
+            normal, 
+            color
+        );
+    }
+
+    triangles
+}
@@ -1,4 +1,8 @@
 pub mod entity;
 pub mod tile;
 pub mod terrain;
-pub mod controller;
\ No newline at end of file
+pub mod controller;
+pub mod mesh;
+pub mod chaser;
+pub mod swarm;
+pub mod guard;
@@ -0,0 +1,71 @@
+use block_engine_wgpu::{
+    steering, 
+    world
+};
+
+use cgmath::{
+    Point3, 
+    Vector3, 
+    MetricSpace
+};
+
+use crate::util::entity;
+
+// Members flee from threats that come closer than this
+const FLEE_RADIUS: f32 = 3.0;
+
+// Floating entities that wander around together, keeping away from walls and threats
+pub struct Swarm {
+    members: Vec<(world::EntityHandle, steering::Steering)>
+}
+
+impl Swarm {
+    pub fn spawn(world: &mut world::World, center: Point3<f32>, count: usize) -> Self {
+        let members = (0..count)
+            .map(|index| {
+                let angle = index as f32 / count as f32 * std::f32::consts::TAU;
+                let offset = Vector3::new(angle.cos(), 0.0, angle.sin());
+
+                let handle = world.add_entity(entity::PlaceholderEntity {
+                    center: center + offset,
+                    color: [0.3, 0.6, 1.0],
+                    light: None,
+                    velocity: offset * 0.05,
+                    collisions: (false, false, false).into(),
+                    mass: 0.5,
+                    linear_drag: 0.02,
+                    gravity_scale: 0.0,
+                    collider: world::Collider::Sphere { radius: 0.2 },
+                    collision_mode: world::CollisionMode::Solid,
+                }, None);
+
+                (handle, steering::Steering::new(Default::default(), index as u32 + 1))
+            } )
+            .collect();
+
+        Self { members }
+    }
+
+    pub fn update(&mut self, world: &mut world::World, threat: &world::EntityHandle) {
+        self.members.retain(|(handle, _)| world.contains_entity_handle(handle));
+
+        for (handle, steering) in self.members.iter_mut() {
+            let force = {
+                let entity = handle.borrow();
+                let threat = threat.borrow();
+
+                let mut force = steering.wander(&*entity) * 0.5 + 
+                    steering.flock(world, handle, Default::default()) + 
+                    steering.avoid_obstacles(world, &*entity) * 2.0;
+
+                if entity.center().distance(threat.center()) < FLEE_RADIUS {
+                    force += steering.evade(&*entity, &*threat) * 2.0;
+                }
+
+                force
+            };
+
+            steering.apply(handle, force);
+        }
+    }
+}
@@ -2,16 +2,122 @@ use block_engine_wgpu::world;
 
 use crate::util::tile;
 
+// Grass has almost no highlight
+const GRASS: world::Material = world::Material {
+    shininess: 8.0,
+    specular_strength: 0.1,
+    ambient: 1.0,
+    emissive: [0.0; 3]
+};
+
+// Glass has a small, bright highlight
+const GLASS: world::Material = world::Material {
+    shininess: 96.0,
+    specular_strength: 1.0,
+    ambient: 1.0,
+    emissive: [0.0; 3]
+};
+
+// Surfaces with unusual physical materials, placed on the floor of the arena
+fn floor_material(x: i16, z: i16) -> Option<([f32; 3], world::PhysicalMaterial, world::Material)> {
+    match (x, z) {
+        // Ice
+        (2..=6, -6..=-2) => Some(([0.6, 0.8, 1.0], world::PhysicalMaterial {
+            friction: 0.01,
+            ..Default::default()
+        }, world::Material { shininess: 64.0, ..Default::default() } )),
+        // Mud
+        (-6..=-2, -6..=-2) => Some(([0.4, 0.3, 0.2], world::PhysicalMaterial {
+            friction: 0.6,
+            ..Default::default()
+        }, world::Material { specular_strength: 0.0, ..Default::default() } )),
+        // Trampoline
+        (-6..=-5, 4..=5) => Some(([1.0, 0.4, 0.8], world::PhysicalMaterial {
+            restitution: 0.9,
+            ..Default::default()
+        }, world::Material::PLASTIC )),
+        // Conveyor belt
+        (-8..=8, 7) => Some(([0.9, 0.8, 0.2], world::PhysicalMaterial {
+            friction: 0.3,
+            conveyor: Some((0.1, 0.0, 0.0).into()),
+            ..Default::default()
+        }, world::Material::METAL )),
+        _ => None
+    }
+}
+
+// Grass on the top of the floor and dirt below, or None if the world has no atlas to draw them from
+fn grass_textures(mesh: &world::World) -> Option<tile::CubeTextures> {
+    let atlas = mesh.texture_atlas()?;
+
+    Some(tile::CubeTextures {
+        top: atlas.region("grass_top")?,
+        bottom: atlas.region("dirt")?,
+        sides: atlas.region("grass_side")?
+    } )
+}
+
+fn stone_textures(mesh: &world::World) -> Option<tile::CubeTextures> {
+    let stone = mesh.texture_atlas()?.region("stone")?;
+
+    Some(tile::CubeTextures { top: stone, bottom: stone, sides: stone } )
+}
+
+// Adds the textures to a cube when there are any
+fn textured(cube: tile::Cube, textures: Option<tile::CubeTextures>) -> tile::Cube {
+    match textures {
+        Some(textures) => cube.with_textures(textures),
+        None => cube
+    }
+}
 
 pub fn generate(mesh: &mut world::World) {
+    let grass = grass_textures(mesh);
+    let stone = stone_textures(mesh);
+
     let mut height;
     for x in -10i16..10 {
         for y in -10i16..10 {
             height = match (x, y) { (-10 | 9, ..) | (.., -10 | 9) => 1, _ => 0 };
-            mesh.add_tile(tile::Cube::new(
-                (x, height, y).into(),
-                [1.0; 3]
-            ));
+
+            let position = (x, height, y).into();
+
+            // Leave a pit in the floor
+            if let (4..=5, 3..=4) = (x, y) {
+                mesh.add_tile(tile::Gap::new(position));
+                continue;
+            }
+
+            mesh.add_tile(match floor_material(x, y) {
+                Some((color, physical_material, material)) => {
+                    tile::Cube::new(position, color)
+                        .with_physical_material(physical_material)
+                        .with_material(material)
+                },
+                None => textured(tile::Cube::new(position, [1.0; 3]), grass).with_material(GRASS)
+            } );
         } 
     }
+
+    // A ramp leading up onto a ledge
+    mesh.add_tile(tile::Ramp::new((-1, 1, -8).into(), [0.7; 3], world::Facing::PositiveX));
+    for x in 0..=2 {
+        mesh.add_tile(textured(tile::Cube::new((x, 1, -8).into(), [1.0; 3]), stone).with_material(world::Material::STONE));
+    }
+
+    // A glass wall further along the ledge, which the floor can be seen through
+    for x in 4..=6 {
+        for y in 1..=2 {
+            mesh.add_tile(
+                tile::Cube::new((x, y, -8).into(), [0.7, 0.9, 1.0])
+                    .with_alpha(0.35)
+                    .with_material(GLASS)
+            );
+        }
+    }
+
+    // Platforms that can be jumped through from below
+    for x in 0..=2 {
+        mesh.add_tile(tile::Platform::new((x, 1, -6).into(), [0.5, 0.9, 0.5]));
+    }
 }
\ No newline at end of file
@@ -1,11 +1,24 @@
-use block_engine_wgpu::{world, Vertex};
+use block_engine_wgpu::{world, texture::TextureRegion, Vertex};
 use cgmath::Point3;
 
+// Regions of the texture atlas drawn on each face of a cube
+#[derive(Clone, Copy, Debug)]
+pub struct CubeTextures {
+    pub top: TextureRegion,
+    pub bottom: TextureRegion,
+    pub sides: TextureRegion
+}
+
 pub struct Cube {
     pub(crate) position: Point3<i16>,
     pub(crate) hw: f32,
     pub(crate) color: [f32; 3],
-    pub(crate) light: Option<[f32; 4]>
+    pub(crate) alpha: f32,
+    pub(crate) light: Option<[f32; 4]>,
+    pub(crate) textures: Option<CubeTextures>,
+    pub(crate) material: world::Material,
+    pub(crate) physical_material: world::PhysicalMaterial,
+    pub(crate) collision_mode: world::CollisionMode
 }
 
 impl Default for Cube {
@@ -14,7 +27,12 @@ impl Default for Cube {
             position: [0, 0, 0].into(), 
             hw: 0.5,
             color: [0.3, 0.3, 0.8],
-            light: None
+            alpha: 1.0,
+            light: None,
+            textures: None,
+            material: world::Material::default(),
+            physical_material: world::PhysicalMaterial::default(),
+            collision_mode: world::CollisionMode::Solid
         }
     }
 }
@@ -30,7 +48,44 @@ impl Cube {
 
 impl Cube {
     pub fn new(position: Point3<i16>, color: [f32; 3]) -> Self {
-        Self { position, hw: 0.5, color, light: None }
+        Self { 
+            position, 
+            hw: 0.5, 
+            color, 
+            alpha: 1.0,
+            light: None, 
+            textures: None,
+            material: world::Material::default(),
+            physical_material: world::PhysicalMaterial::default(),
+            collision_mode: world::CollisionMode::Solid
+        }
+    }
+
+    pub fn with_physical_material(mut self, physical_material: world::PhysicalMaterial) -> Self {
+        self.physical_material = physical_material;
+        self
+    }
+
+    // The vertex color tints the textures
+    pub fn with_textures(mut self, textures: CubeTextures) -> Self {
+        self.textures = Some(textures);
+        self
+    }
+
+    // Cubes with an alpha below 1 can be seen through
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn with_material(mut self, material: world::Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn with_collision_mode(mut self, collision_mode: world::CollisionMode) -> Self {
+        self.collision_mode = collision_mode;
+        self
     }
 }
 
@@ -38,17 +93,10 @@ impl world::Tile for Cube {
     fn position(&self) -> Point3<i16> { self.position }
 
     fn set_position(&mut self, position: Point3<i16>) { self.position = position; }
-}
 
-impl world::Drawable for Cube {
-    fn center(&self) -> Point3<f32> { self.position.cast::<f32>().unwrap() }
-    fn set_center(&mut self, center: Point3<f32>) { self.position = center.cast::<i16>().unwrap(); }
+    fn physical_material(&self) -> world::PhysicalMaterial { self.physical_material }
 
-    fn color(&self) -> [f32; 3] { self.color }
-    fn set_color(&mut self, color: [f32; 3]) { self.color = color; }    
-
-    fn light(&self) -> Option<[f32; 4]> { self.light }
-    fn set_light(&mut self, light: [f32; 4]) { self.light = Some(light); }
+    fn collision_mode(&self) -> world::CollisionMode { self.collision_mode }
 
     fn build_object_data(&self) -> world::Triangles {
         let center = Point3::new(
@@ -68,62 +116,85 @@ impl world::Drawable for Cube {
             [ center.x + self.hw, center.y + self.hw, center.z - self.hw ]
         ];
 
-        let normals = if self.light.is_none() {
-            [
-                Self::FRONT, 
-                Self::BACK, 
-                Self::LEFT, 
-                Self::RIGHT, 
-                Self::TOP, 
-                Self::BOTTOM
-            ]
-        } else {
-            [
-                Self::BACK,
-                Self::FRONT,
-                Self::RIGHT,
-                Self::LEFT,
-                Self::BOTTOM,
-                Self::TOP
-            ]
+        let normals = [
+            Self::FRONT, 
+            Self::BACK, 
+            Self::LEFT, 
+            Self::RIGHT, 
+            Self::TOP, 
+            Self::BOTTOM
+        ];
+
+        // Texture coordinates of each face's corners, so that textures are upright on the sides
+        let uvs = [
+            [[0.0, 1.0], [1.0, 1.0], [0.0, 0.0], [1.0, 0.0]],
+            [[1.0, 1.0], [0.0, 1.0], [1.0, 0.0], [0.0, 0.0]],
+            [[0.0, 1.0], [0.0, 0.0], [1.0, 1.0], [1.0, 0.0]],
+            [[1.0, 1.0], [1.0, 0.0], [0.0, 1.0], [0.0, 0.0]],
+            [[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]],
+            [[0.0, 1.0], [0.0, 0.0], [1.0, 1.0], [1.0, 0.0]]
+        ];
+
+        let regions = match self.textures {
+            Some(textures) => [
+                Some(textures.sides),
+                Some(textures.sides),
+                Some(textures.sides),
+                Some(textures.sides),
+                Some(textures.top),
+                Some(textures.bottom)
+            ],
+            None => [None; 6]
+        };
+
+        let [r, g, b] = self.color;
+        let vertex = |position: usize, face: usize, corner: usize| {
+            let [u, v] = uvs[face][corner];
+            Vertex {
+                position: positions[position],
+                color: [r, g, b, self.alpha],
+                normal: normals[face],
+                uv: regions[face].map_or(Vertex::UNTEXTURED, |region| region.uv(u, v)),
+                ..Default::default()
+            }
         };
 
         let vertices = vec![
             // front
-            Vertex { position: positions[0], color: self.color, normal: normals[0] },
-            Vertex { position: positions[2], color: self.color, normal: normals[0] },
-            Vertex { position: positions[1], color: self.color, normal: normals[0] },
-            Vertex { position: positions[3], color: self.color, normal: normals[0] },
+            vertex(0, 0, 0),
+            vertex(2, 0, 1),
+            vertex(1, 0, 2),
+            vertex(3, 0, 3),
 
             // back
-            Vertex { position: positions[4], color: self.color, normal: normals[1] },
-            Vertex { position: positions[6], color: self.color, normal: normals[1] },
-            Vertex { position: positions[5], color: self.color, normal: normals[1] },
-            Vertex { position: positions[7], color: self.color, normal: normals[1] },
+            vertex(4, 1, 0),
+            vertex(6, 1, 1),
+            vertex(5, 1, 2),
+            vertex(7, 1, 3),
 
             // left
-            Vertex { position: positions[4], color: self.color, normal: normals[2] },
-            Vertex { position: positions[5], color: self.color, normal: normals[2] },
-            Vertex { position: positions[0], color: self.color, normal: normals[2] },
-            Vertex { position: positions[1], color: self.color, normal: normals[2] },
+            vertex(4, 2, 0),
+            vertex(5, 2, 1),
+            vertex(0, 2, 2),
+            vertex(1, 2, 3),
 
             // right
-            Vertex { position: positions[6], color: self.color, normal: normals[3] },
-            Vertex { position: positions[7], color: self.color, normal: normals[3] },
-            Vertex { position: positions[2], color: self.color, normal: normals[3] },
-            Vertex { position: positions[3], color: self.color, normal: normals[3] },
+            vertex(6, 3, 0),
+            vertex(7, 3, 1),
+            vertex(2, 3, 2),
+            vertex(3, 3, 3),
 
             // top
-            Vertex { position: positions[5], color: self.color, normal: normals[4] },
-            Vertex { position: positions[1], color: self.color, normal: normals[4] },
-            Vertex { position: positions[7], color: self.color, normal: normals[4] },
-            Vertex { position: positions[3], color: self.color, normal: normals[4] },
+            vertex(5, 4, 0),
+            vertex(1, 4, 1),
+            vertex(7, 4, 2),
+            vertex(3, 4, 3),
 
             // bottom
-            Vertex { position: positions[4], color: self.color, normal: normals[5] },
-            Vertex { position: positions[0], color: self.color, normal: normals[5] },
-            Vertex { position: positions[6], color: self.color, normal: normals[5] },
-            Vertex { position: positions[2], color: self.color, normal: normals[5] }
+            vertex(4, 5, 0),
+            vertex(0, 5, 1),
+            vertex(6, 5, 2),
+            vertex(2, 5, 3)
         ];
 
         let indices = vec![
@@ -137,4 +208,19 @@ impl world::Drawable for Cube {
 
         world::Triangles { vertices, indices }
     }
-}
\ No newline at end of file
+}
+
+impl world::Drawable for Cube {
+    fn center(&self) -> Point3<f32> { self.position.cast::<f32>().unwrap() }
+    fn set_center(&mut self, center: Point3<f32>) { self.position = center.cast::<i16>().unwrap(); }
+
+    fn color(&self) -> [f32; 3] { self.color }
+    fn set_color(&mut self, color: [f32; 3]) { self.color = color; }    
+
+    fn light(&self) -> Option<[f32; 4]> { self.light }
+    fn set_light(&mut self, light: [f32; 4]) { self.light = Some(light); }
+
+    fn alpha(&self) -> f32 { self.alpha }
+
+    fn material(&self) -> world::Material { self.material }
+}
@@ -17,7 +17,6 @@ impl Default for Gap {
 }
 
 impl Gap {
-    #[allow(unused)]
     pub fn new(position: Point3<i16>) -> Self {
         Self { position, light: None }
     }
@@ -26,6 +25,13 @@ impl Gap {
 impl world::Tile for Gap { 
     fn position(&self) -> Point3<i16> { self.position }
     fn set_position(&mut self, position: Point3<i16>) { self.position = position; }
+
+    // Gaps occupy a cell without blocking anything
+    fn collision_mode(&self) -> world::CollisionMode { world::CollisionMode::Ghost }
+
+    fn build_object_data(&self) -> world::Triangles {
+        world::Triangles { vertices: Vec::new(), indices: Vec::new() }
+    }
 }
 
 impl world::Drawable for Gap {
@@ -37,8 +43,4 @@ impl world::Drawable for Gap {
 
     fn light(&self) -> Option<[f32; 4]> { self.light }
     fn set_light(&mut self, light: [f32; 4]) { self.light = Some(light); }
-
-    fn build_object_data(&self) -> world::Triangles {
-        world::Triangles { vertices: Vec::new(), indices: Vec::new() }
-    }
-}
\ No newline at end of file
+}
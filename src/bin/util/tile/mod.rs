@@ -1,5 +1,11 @@
 mod cube;
-pub use cube::Cube;
+pub use cube::{ Cube, CubeTextures };
 
 mod gap;
-pub use gap::Gap;
\ No newline at end of file
+pub use gap::Gap;
+
+mod ramp;
+pub use ramp::Ramp;
+
+mod platform;
+pub use platform::Platform;
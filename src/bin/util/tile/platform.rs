@@ -0,0 +1,49 @@
+use block_engine_wgpu::world;
+use cgmath::{
+    Point3, 
+    Vector3
+};
+
+use crate::util::mesh;
+
+// Thin slab at the top of its cell that can be jumped through from below
+pub struct Platform {
+    pub(crate) position: Point3<i16>,
+    pub(crate) color: [f32; 3],
+    pub(crate) light: Option<[f32; 4]>
+}
+
+impl Platform {
+    pub fn new(position: Point3<i16>, color: [f32; 3]) -> Self {
+        Self { position, color, light: None }
+    }
+}
+
+impl world::Tile for Platform { 
+    fn position(&self) -> Point3<i16> { self.position }
+    fn set_position(&mut self, position: Point3<i16>) { self.position = position; }
+
+    fn collision_shape(&self) -> world::TileShape { world::TileShape::OneWay }
+
+    fn build_object_data(&self) -> world::Triangles {
+        let thickness = world::TileShape::ONE_WAY_THICKNESS;
+        let center = self.position.cast::<f32>().unwrap();
+
+        mesh::build_box(
+            center + Vector3::new(-0.5, 0.5 - thickness, -0.5), 
+            center + Vector3::new(0.5, 0.5, 0.5), 
+            self.color
+        )
+    }
+}
+
+impl world::Drawable for Platform {
+    fn center(&self) -> Point3<f32> { self.position.cast::<f32>().unwrap() }
+    fn set_center(&mut self, center: Point3<f32>) { self.position = center.cast::<i16>().unwrap(); }
+
+    fn color(&self) -> [f32; 3] { self.color }
+    fn set_color(&mut self, color: [f32; 3]) { self.color = color; }
+
+    fn light(&self) -> Option<[f32; 4]> { self.light }
+    fn set_light(&mut self, light: [f32; 4]) { self.light = Some(light); }
+}
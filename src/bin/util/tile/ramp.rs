@@ -0,0 +1,99 @@
+use block_engine_wgpu::world;
+use cgmath::{
+    Point3, 
+    Vector3, 
+    InnerSpace
+};
+
+use crate::util::mesh;
+
+pub struct Ramp {
+    pub(crate) position: Point3<i16>,
+    pub(crate) color: [f32; 3],
+    pub(crate) light: Option<[f32; 4]>,
+    pub(crate) facing: world::Facing
+}
+
+impl Ramp {
+    pub fn new(position: Point3<i16>, color: [f32; 3], facing: world::Facing) -> Self {
+        Self { position, color, light: None, facing }
+    }
+
+    // Rotates a point of a ramp rising towards positive x so that it rises towards `facing`
+    fn orient(&self, v: Vector3<f32>) -> Vector3<f32> {
+        match self.facing {
+            world::Facing::PositiveX => v,
+            world::Facing::NegativeX => Vector3::new(-v.x, v.y, -v.z),
+            world::Facing::PositiveZ => Vector3::new(-v.z, v.y, v.x),
+            world::Facing::NegativeZ => Vector3::new(v.z, v.y, -v.x),
+        }
+    }
+}
+
+impl world::Tile for Ramp { 
+    fn position(&self) -> Point3<i16> { self.position }
+    fn set_position(&mut self, position: Point3<i16>) { self.position = position; }
+
+    fn collision_shape(&self) -> world::TileShape { world::TileShape::Ramp { facing: self.facing } }
+
+    fn build_object_data(&self) -> world::Triangles {
+        let center = self.position.cast::<f32>().unwrap();
+        let corner = |x: f32, y: f32, z: f32| center + self.orient(Vector3::new(x, y, z));
+        let normal = |x: f32, y: f32, z: f32| self.orient(Vector3::new(x, y, z));
+
+        let mut triangles = world::Triangles { 
+            vertices: Vec::with_capacity(18), 
+            indices: Vec::with_capacity(24) 
+        };
+
+        // Bottom
+        mesh::push_quad(&mut triangles, [
+            corner(-0.5, -0.5, -0.5), 
+            corner(0.5, -0.5, -0.5), 
+            corner(0.5, -0.5, 0.5), 
+            corner(-0.5, -0.5, 0.5)
+        ], normal(0.0, -1.0, 0.0), self.color);
+
+        // Tall side
+        mesh::push_quad(&mut triangles, [
+            corner(0.5, -0.5, 0.5), 
+            corner(0.5, -0.5, -0.5), 
+            corner(0.5, 0.5, -0.5), 
+            corner(0.5, 0.5, 0.5)
+        ], normal(1.0, 0.0, 0.0), self.color);
+
+        // Slope
+        mesh::push_quad(&mut triangles, [
+            corner(-0.5, -0.5, 0.5), 
+            corner(0.5, 0.5, 0.5), 
+            corner(0.5, 0.5, -0.5), 
+            corner(-0.5, -0.5, -0.5)
+        ], normal(-1.0, 1.0, 0.0).normalize(), self.color);
+
+        // Triangular sides
+        mesh::push_triangle(&mut triangles, [
+            corner(-0.5, -0.5, 0.5), 
+            corner(0.5, -0.5, 0.5), 
+            corner(0.5, 0.5, 0.5)
+        ], normal(0.0, 0.0, 1.0), self.color);
+
+        mesh::push_triangle(&mut triangles, [
+            corner(-0.5, -0.5, -0.5), 
+            corner(0.5, 0.5, -0.5), 
+            corner(0.5, -0.5, -0.5)
+        ], normal(0.0, 0.0, -1.0), self.color);
+
+        triangles
+    }
+}
+
+impl world::Drawable for Ramp {
+    fn center(&self) -> Point3<f32> { self.position.cast::<f32>().unwrap() }
+    fn set_center(&mut self, center: Point3<f32>) { self.position = center.cast::<i16>().unwrap(); }
+
+    fn color(&self) -> [f32; 3] { self.color }
+    fn set_color(&mut self, color: [f32; 3]) { self.color = color; }
+
+    fn light(&self) -> Option<[f32; 4]> { self.light }
+    fn set_light(&mut self, light: [f32; 4]) { self.light = Some(light); }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use cgmath::Point3;
+
+use crate::world::{
+    World,
+    EntityHandle
+};
+
+#[derive(Clone)]
+pub enum Value {
+    Bool(bool),
+    Number(f32),
+    Point(Point3<f32>),
+    Entity(EntityHandle),
+    Text(String)
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self { Self::Bool(value) }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self { Self::Number(value) }
+}
+
+impl From<Point3<f32>> for Value {
+    fn from(value: Point3<f32>) -> Self { Self::Point(value) }
+}
+
+impl From<EntityHandle> for Value {
+    fn from(value: EntityHandle) -> Self { Self::Entity(value) }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self { Self::Text(value) }
+}
+
+// Named values shared by the nodes of a tree
+#[derive(Clone, Default)]
+pub struct Blackboard {
+    values: HashMap<String, Value>
+}
+
+impl Blackboard {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    pub fn insert(&mut self, key: &str, value: impl Into<Value>) {
+        self.values.insert(key.to_string(), value.into());
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.values.remove(key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    pub fn number(&self, key: &str) -> Option<f32> {
+        match self.values.get(key) {
+            Some(Value::Number(number)) => Some(*number),
+            _ => None
+        }
+    }
+
+    pub fn entity(&self, key: &str) -> Option<EntityHandle> {
+        match self.values.get(key) {
+            Some(Value::Entity(entity)) => Some(entity.clone()),
+            _ => None
+        }
+    }
+
+    // The position of a point or an entity stored under the key
+    // Falls back to the entity with the key as its tag in the world
+    pub fn position(&self, world: &World, key: &str) -> Option<Point3<f32>> {
+        match self.values.get(key) {
+            Some(Value::Point(point)) => Some(*point),
+            Some(Value::Entity(entity)) => Some(entity.borrow().center()),
+            Some(..) => None,
+            None => world.get_entity(key).map(|entity| entity.borrow().center())
+        }
+    }
+}
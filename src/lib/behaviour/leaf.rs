@@ -0,0 +1,125 @@
+use cgmath::{
+    Point3,
+    Vector3,
+    InnerSpace,
+    MetricSpace
+};
+
+use crate::steering::{
+    Steering,
+    SteeringConfig
+};
+
+use super::{
+    Behaviour,
+    Context,
+    Status
+};
+
+// Height above an entity's center that it looks from
+const EYE_HEIGHT: f32 = 0.25;
+
+// Succeeds if the target is close enough and no solid tile blocks the view of it
+// The target is looked up in the blackboard, or by its tag in the world
+pub struct CanSee {
+    target: String,
+    max_distance: f32,
+    // Where to store the target's position each time it is seen
+    remember_as: Option<String>
+}
+
+impl CanSee {
+    pub fn new(target: &str, max_distance: f32, remember_as: Option<&str>) -> Self {
+        Self {
+            target: target.to_string(),
+            max_distance,
+            remember_as: remember_as.map(str::to_string)
+        }
+    }
+}
+
+impl Behaviour for CanSee {
+    fn tick(&mut self, context: &mut Context) -> Status {
+        let target = match context.blackboard.position(context.world, &self.target) {
+            Some(target) => target,
+            None => return Status::Failure
+        };
+
+        let eye = context.entity.borrow().center() + Vector3::unit_y() * EYE_HEIGHT;
+        if eye.distance(target) > self.max_distance || !context.world.has_line_of_sight(eye, target) {
+            return Status::Failure;
+        }
+
+        if let Some(key) = self.remember_as.as_ref() {
+            context.blackboard.insert(key, target);
+        }
+
+        Status::Success
+    }
+}
+
+// Steers the entity towards the target until it is within the given horizontal distance
+// Fails if the target doesn't exist
+pub struct MoveTo {
+    target: String,
+    tolerance: f32,
+    steering: Steering
+}
+
+impl MoveTo {
+    pub fn new(target: &str, tolerance: f32, config: SteeringConfig) -> Self {
+        Self {
+            target: target.to_string(),
+            tolerance,
+            steering: Steering::new(config, 1)
+        }
+    }
+}
+
+impl Behaviour for MoveTo {
+    fn tick(&mut self, context: &mut Context) -> Status {
+        let target = match context.blackboard.position(context.world, &self.target) {
+            Some(target) => target,
+            None => return Status::Failure
+        };
+
+        let center = context.entity.borrow().center();
+        let offset = Vector3::new(target.x - center.x, 0.0, target.z - center.z);
+        if offset.magnitude() <= self.tolerance {
+            return Status::Success;
+        }
+
+        let force = self.steering.arrive(&*context.entity.borrow(), Point3::new(target.x, center.y, target.z));
+        self.steering.apply(&context.entity, force);
+
+        Status::Running
+    }
+}
+
+// Runs for the given number of ticks, then succeeds
+pub struct Wait {
+    ticks: u32,
+    elapsed: u32
+}
+
+impl Wait {
+    pub fn new(ticks: u32) -> Self {
+        Self { ticks, elapsed: 0 }
+    }
+}
+
+impl Behaviour for Wait {
+    fn tick(&mut self, _context: &mut Context) -> Status {
+        self.elapsed += 1;
+        if self.elapsed >= self.ticks {
+            self.elapsed = 0;
+            return Status::Success;
+        }
+
+        Status::Running
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = 0;
+    }
+}
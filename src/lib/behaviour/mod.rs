@@ -0,0 +1,73 @@
+pub(crate) mod blackboard;
+pub use blackboard::{ Blackboard, Value };
+
+pub(crate) mod node;
+pub use node::{ 
+    Sequence, 
+    ReactiveSequence, 
+    Selector, 
+    Decorator, 
+    DecoratorKind, 
+    Action 
+};
+
+pub(crate) mod leaf;
+pub use leaf::{ CanSee, MoveTo, Wait };
+
+pub(crate) mod parse;
+pub use parse::{ Expr, ParseError, TreeParser };
+
+use crate::world::{
+    World,
+    EntityHandle
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    // The node needs to be ticked again before it finishes
+    Running
+}
+
+// Everything a node can see and change while it is ticked
+pub struct Context<'c, 'a> {
+    pub world: &'c mut World<'a>,
+    // The entity the tree belongs to
+    pub entity: EntityHandle,
+    pub blackboard: &'c mut Blackboard
+}
+
+pub trait Behaviour {
+    fn tick(&mut self, context: &mut Context) -> Status;
+
+    // Called when a running node is interrupted, so that it starts over the next time it is ticked
+    fn reset(&mut self) {  }
+}
+
+// A tree of behaviours along with the memory they share
+// Should be ticked once per tick, during the game update
+pub struct BehaviourTree {
+    root: Box<dyn Behaviour>,
+    pub blackboard: Blackboard
+}
+
+impl BehaviourTree {
+    pub fn new(root: Box<dyn Behaviour>) -> Self {
+        Self { root, blackboard: Blackboard::default() }
+    }
+
+    pub fn tick(&mut self, world: &mut World, entity: &EntityHandle) -> Status {
+        let mut context = Context {
+            world,
+            entity: entity.clone(),
+            blackboard: &mut self.blackboard
+        };
+
+        self.root.tick(&mut context)
+    }
+
+    pub fn reset(&mut self) {
+        self.root.reset();
+    }
+}
@@ -0,0 +1,206 @@
+use super::{
+    Behaviour,
+    Context,
+    Status
+};
+
+// Ticks its children in order until one of them fails
+// A running child is resumed on the next tick, without ticking the children before it again
+pub struct Sequence {
+    children: Vec<Box<dyn Behaviour>>,
+    current: usize
+}
+
+impl Sequence {
+    pub fn new(children: Vec<Box<dyn Behaviour>>) -> Self {
+        Self { children, current: 0 }
+    }
+}
+
+impl Behaviour for Sequence {
+    fn tick(&mut self, context: &mut Context) -> Status {
+        while self.current < self.children.len() {
+            match self.children[self.current].tick(context) {
+                Status::Success => self.current += 1,
+                Status::Running => return Status::Running,
+                Status::Failure => {
+                    self.current = 0;
+                    return Status::Failure;
+                }
+            }
+        }
+
+        self.current = 0;
+        Status::Success
+    }
+
+    fn reset(&mut self) {
+        if let Some(child) = self.children.get_mut(self.current) {
+            child.reset();
+        }
+
+        self.current = 0;
+    }
+}
+
+// Ticks its children in order until one of them fails, starting from the first child on every tick
+// Useful for conditions that must keep holding while an action runs
+pub struct ReactiveSequence {
+    children: Vec<Box<dyn Behaviour>>,
+    running: Option<usize>
+}
+
+impl ReactiveSequence {
+    pub fn new(children: Vec<Box<dyn Behaviour>>) -> Self {
+        Self { children, running: None }
+    }
+}
+
+impl Behaviour for ReactiveSequence {
+    fn tick(&mut self, context: &mut Context) -> Status {
+        for index in 0..self.children.len() {
+            let status = self.children[index].tick(context);
+            if status == Status::Success { continue; }
+
+            // A child that was running before is abandoned if an earlier child stops the sequence
+            if let Some(running) = self.running.filter(|running| *running != index) {
+                self.children[running].reset();
+            }
+
+            self.running = Some(index).filter(|_| status == Status::Running);
+            return status;
+        }
+
+        self.running = None;
+        Status::Success
+    }
+
+    fn reset(&mut self) {
+        if let Some(running) = self.running.take() {
+            self.children[running].reset();
+        }
+    }
+}
+
+// Ticks its children in order until one of them succeeds, starting from the first child on every tick
+// Earlier children take priority, interrupting later ones that were running
+pub struct Selector {
+    children: Vec<Box<dyn Behaviour>>,
+    running: Option<usize>
+}
+
+impl Selector {
+    pub fn new(children: Vec<Box<dyn Behaviour>>) -> Self {
+        Self { children, running: None }
+    }
+}
+
+impl Behaviour for Selector {
+    fn tick(&mut self, context: &mut Context) -> Status {
+        for index in 0..self.children.len() {
+            let status = self.children[index].tick(context);
+            if status == Status::Failure { continue; }
+
+            if let Some(running) = self.running.filter(|running| *running != index) {
+                self.children[running].reset();
+            }
+
+            self.running = Some(index).filter(|_| status == Status::Running);
+            return status;
+        }
+
+        self.running = None;
+        Status::Failure
+    }
+
+    fn reset(&mut self) {
+        if let Some(running) = self.running.take() {
+            self.children[running].reset();
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecoratorKind {
+    // Swaps success and failure
+    Invert,
+    // Always succeeds once the child finishes
+    Succeed,
+    // Always fails once the child finishes
+    Fail,
+    // Runs the child again each time it succeeds, up to the given number of times or forever
+    // Fails as soon as the child fails
+    Repeat(Option<u32>),
+    // Runs the child again each time it fails, up to the given number of times or forever
+    // Succeeds as soon as the child succeeds
+    Retry(Option<u32>)
+}
+
+// Changes the result of a single child
+pub struct Decorator {
+    kind: DecoratorKind,
+    child: Box<dyn Behaviour>,
+    count: u32
+}
+
+impl Decorator {
+    pub fn new(kind: DecoratorKind, child: Box<dyn Behaviour>) -> Self {
+        Self { kind, child, count: 0 }
+    }
+}
+
+impl Behaviour for Decorator {
+    fn tick(&mut self, context: &mut Context) -> Status {
+        let status = self.child.tick(context);
+        if status == Status::Running {
+            return Status::Running;
+        }
+
+        let (limit, until) = match self.kind {
+            DecoratorKind::Invert => return match status {
+                Status::Success => Status::Failure,
+                _ => Status::Success
+            },
+            DecoratorKind::Succeed => return Status::Success,
+            DecoratorKind::Fail => return Status::Failure,
+            DecoratorKind::Repeat(limit) => (limit, Status::Failure),
+            DecoratorKind::Retry(limit) => (limit, Status::Success)
+        };
+
+        if status == until {
+            self.count = 0;
+            return status;
+        }
+
+        // The child runs again on the next tick, so that a child that finishes instantly can't stall the game
+        self.count += 1;
+        if limit.is_some_and(|limit| self.count >= limit) {
+            self.count = 0;
+            return status;
+        }
+
+        Status::Running
+    }
+
+    fn reset(&mut self) {
+        self.child.reset();
+        self.count = 0;
+    }
+}
+
+// Leaf that runs a closure, for behaviours that are specific to a game
+pub struct Action<F> {
+    action: F
+}
+
+impl<F> Action<F> where F: FnMut(&mut Context) -> Status {
+    pub fn new(action: F) -> Self {
+        Self { action }
+    }
+}
+
+impl<F> Behaviour for Action<F> where F: FnMut(&mut Context) -> Status {
+    fn tick(&mut self, context: &mut Context) -> Status {
+        (self.action)(context)
+    }
+}
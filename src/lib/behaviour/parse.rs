@@ -0,0 +1,225 @@
+use std::{
+    collections::HashMap,
+    fmt
+};
+
+use crate::steering::SteeringConfig;
+
+use super::{
+    Behaviour,
+    BehaviourTree,
+    Sequence,
+    ReactiveSequence,
+    Selector,
+    Decorator,
+    DecoratorKind,
+    CanSee,
+    MoveTo,
+    Wait
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    // Byte offset into the source where the problem was found
+    pub position: usize
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {  }
+
+// An S-expression, along with its byte offset into the source
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    List(Vec<Expr>, usize),
+    Symbol(String, usize),
+    Text(String, usize),
+    Number(f32, usize)
+}
+
+impl Expr {
+    pub fn position(&self) -> usize {
+        match self {
+            Self::List(_, position) |
+            Self::Symbol(_, position) |
+            Self::Text(_, position) |
+            Self::Number(_, position) => *position
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError { message: message.into(), position: self.position() }
+    }
+
+    pub fn as_number(&self) -> Result<f32, ParseError> {
+        match self {
+            Self::Number(number, _) => Ok(*number),
+            _ => Err(self.error("expected a number"))
+        }
+    }
+
+    pub fn as_text(&self) -> Result<&str, ParseError> {
+        match self {
+            Self::Text(text, _) => Ok(text),
+            _ => Err(self.error("expected a string"))
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Expr>, ParseError> {
+    let mut stack: Vec<(Vec<Expr>, usize)> = vec![(Vec::new(), 0)];
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((position, c)) = chars.next() {
+        match c {
+            // Comments run until the end of the line
+            ';' => while chars.next_if(|(_, c)| *c != '\n').is_some() {  },
+            '(' => stack.push((Vec::new(), position)),
+            ')' => {
+                let (list, start) = stack.pop().filter(|_| !stack.is_empty()).ok_or(ParseError {
+                    message: "unexpected ')'".to_string(),
+                    position
+                } )?;
+
+                stack.last_mut().unwrap().0.push(Expr::List(list, start));
+            },
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, c)) => text.push(c),
+                        None => return Err(ParseError { message: "unterminated string".to_string(), position })
+                    }
+                }
+
+                stack.last_mut().unwrap().0.push(Expr::Text(text, position));
+            },
+            c if c.is_whitespace() => {  },
+            _ => {
+                let mut atom = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(_, c)| !c.is_whitespace() && !"();\"".contains(*c)) {
+                    atom.push(c);
+                }
+
+                stack.last_mut().unwrap().0.push(match atom.parse::<f32>() {
+                    Ok(number) => Expr::Number(number, position),
+                    Err(..) => Expr::Symbol(atom, position)
+                } );
+            }
+        }
+    }
+
+    match stack.pop() {
+        Some((exprs, _)) if stack.is_empty() => Ok(exprs),
+        Some((_, position)) => Err(ParseError { message: "unclosed '('".to_string(), position }),
+        None => unreachable!()
+    }
+}
+
+type LeafConstructor = Box<dyn Fn(&[Expr]) -> Result<Box<dyn Behaviour>, ParseError>>;
+
+// Builds behaviour trees from S-expressions, such as
+//
+//     (selector
+//         (reactive-sequence (can-see "player" 8 "last_seen") (move-to "last_seen" 1))
+//         (sequence (wait 60) (move-to "post" 0.5)))
+//
+// Composites are `sequence`, `reactive-sequence` and `selector`
+// Decorators are `invert`, `succeed`, `fail`, `repeat` and `retry`, which take an optional count before their child
+// Leaves are `can-see`, `move-to`, `wait` and any that are registered
+#[derive(Default)]
+pub struct TreeParser {
+    leaves: HashMap<String, LeafConstructor>,
+    // Used by `move-to` leaves
+    pub steering: SteeringConfig
+}
+
+impl TreeParser {
+    // Adds a leaf that can be used by name, which is built from the arguments it is given
+    pub fn register<F>(&mut self, name: &str, constructor: F) where
+        F: Fn(&[Expr]) -> Result<Box<dyn Behaviour>, ParseError> + 'static {
+        self.leaves.insert(name.to_string(), Box::new(constructor));
+    }
+
+    // The source must contain a single tree
+    pub fn parse(&self, source: &str) -> Result<BehaviourTree, ParseError> {
+        match tokenize(source)?.as_slice() {
+            [root] => Ok(BehaviourTree::new(self.build(root)?)),
+            [] => Err(ParseError { message: "expected a tree".to_string(), position: 0 }),
+            [_, extra, ..] => Err(extra.error("expected a single tree"))
+        }
+    }
+
+    fn build(&self, expr: &Expr) -> Result<Box<dyn Behaviour>, ParseError> {
+        let (name, arguments) = match expr {
+            Expr::List(list, _) => match list.split_first() {
+                Some((Expr::Symbol(name, _), arguments)) => (name.as_str(), arguments),
+                Some((other, _)) => return Err(other.error("expected a node name")),
+                None => return Err(expr.error("empty node"))
+            },
+            // Nodes without arguments can be written without parentheses
+            Expr::Symbol(name, _) => (name.as_str(), &[] as &[Expr]),
+            _ => return Err(expr.error("expected a node"))
+        };
+
+        let children = |arguments: &[Expr]| {
+            arguments.iter().map(|child| self.build(child)).collect::<Result<Vec<_>, _>>()
+        };
+
+        let decorator = |kind: fn(Option<u32>) -> DecoratorKind| -> Result<Box<dyn Behaviour>, ParseError> {
+            match arguments {
+                [child] => Ok(Box::new(Decorator::new(kind(None), self.build(child)?))),
+                [count, child] => Ok(Box::new(Decorator::new(
+                    kind(Some(count.as_number()? as u32)),
+                    self.build(child)?
+                ))),
+                _ => Err(expr.error(format!("'{}' takes a child and an optional count", name)))
+            }
+        };
+
+        let single = |kind: DecoratorKind| -> Result<Box<dyn Behaviour>, ParseError> {
+            match arguments {
+                [child] => Ok(Box::new(Decorator::new(kind, self.build(child)?))),
+                _ => Err(expr.error(format!("'{}' takes a single child", name)))
+            }
+        };
+
+        match name {
+            "sequence" => Ok(Box::new(Sequence::new(children(arguments)?))),
+            "reactive-sequence" => Ok(Box::new(ReactiveSequence::new(children(arguments)?))),
+            "selector" => Ok(Box::new(Selector::new(children(arguments)?))),
+            "invert" => single(DecoratorKind::Invert),
+            "succeed" => single(DecoratorKind::Succeed),
+            "fail" => single(DecoratorKind::Fail),
+            "repeat" => decorator(DecoratorKind::Repeat),
+            "retry" => decorator(DecoratorKind::Retry),
+            "can-see" => match arguments {
+                [target, distance] => Ok(Box::new(CanSee::new(target.as_text()?, distance.as_number()?, None))),
+                [target, distance, remember_as] => Ok(Box::new(CanSee::new(
+                    target.as_text()?,
+                    distance.as_number()?,
+                    Some(remember_as.as_text()?)
+                ))),
+                _ => Err(expr.error("'can-see' takes a target, a distance and an optional key to remember it as"))
+            },
+            "move-to" => match arguments {
+                [target, tolerance] => Ok(Box::new(MoveTo::new(target.as_text()?, tolerance.as_number()?, self.steering))),
+                _ => Err(expr.error("'move-to' takes a target and a tolerance"))
+            },
+            "wait" => match arguments {
+                [ticks] => Ok(Box::new(Wait::new(ticks.as_number()? as u32))),
+                _ => Err(expr.error("'wait' takes a number of ticks"))
+            },
+            _ => match self.leaves.get(name) {
+                Some(constructor) => constructor(arguments),
+                None => Err(expr.error(format!("unknown node '{}'", name)))
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use wgpu::{
+    BufferAddress,
+    BufferUsages,
+    Device,
+    Queue
+};
+
+// Smallest buffer that is created, in bytes
+const MIN_BUFFER_SIZE: BufferAddress = 256;
+
+// A GPU buffer that is kept between ticks
+// Only the contents that changed are written, and the buffer doubles in size when it runs out of room
+pub(crate) struct DynamicBuffer {
+    buffer: wgpu::Buffer,
+    usage: BufferUsages,
+    capacity: BufferAddress
+}
+
+impl DynamicBuffer {
+    pub(crate) fn new(device: &Device, usage: BufferUsages) -> Self {
+        let usage = usage | BufferUsages::COPY_DST;
+
+        Self {
+            buffer: Self::create(device, usage, MIN_BUFFER_SIZE),
+            usage,
+            capacity: MIN_BUFFER_SIZE
+        }
+    }
+
+    fn create(device: &Device, usage: BufferUsages, size: BufferAddress) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage,
+            mapped_at_creation: false
+        } )
+    }
+
+    // Writes the elements from `changed_from` onwards, which are assumed to be the only ones that changed
+    // All of the elements are written if the buffer had to grow, which is reported by the return value
+    pub(crate) fn update<T: bytemuck::Pod>(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        data: &[T],
+        changed_from: usize
+    ) -> bool {
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+        let size = bytes.len() as BufferAddress;
+
+        let grown = size > self.capacity;
+        if grown {
+            self.capacity = size.next_power_of_two();
+            self.buffer = Self::create(device, self.usage, self.capacity);
+        }
+
+        let offset = if grown { 0 } else { (changed_from * std::mem::size_of::<T>()).min(bytes.len()) };
+        if offset < bytes.len() {
+            queue.write_buffer(&self.buffer, offset as BufferAddress, &bytes[offset..]);
+        }
+
+        grown
+    }
+
+    // Replaces all of the contents
+    pub(crate) fn write<T: bytemuck::Pod>(&mut self, device: &Device, queue: &Queue, data: &[T]) -> bool {
+        self.update(device, queue, data, 0)
+    }
+
+    pub(crate) fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+}
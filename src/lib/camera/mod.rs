@@ -3,7 +3,9 @@ use cgmath::{
     Matrix4, 
     SquareMatrix, 
     EuclideanSpace, 
-    Vector3
+    Vector3,
+    InnerSpace,
+    Angle
 };
 
 pub struct Camera {
@@ -97,7 +99,7 @@ impl Camera {
     const ZNEAR: f32 = 0.1;
     const ZFAR: f32 = 1000.0;
 
-    const MATRIX_CORRECTION_FOR_WGPU: Matrix4<f32> = Matrix4::new(
+    pub(crate) const MATRIX_CORRECTION_FOR_WGPU: Matrix4<f32> = Matrix4::new(
         1.0, 0.0, 0.0, 0.0,
         0.0, 1.0, 0.0, 0.0,
         0.0, 0.0, 0.5, 0.0,
@@ -120,6 +122,30 @@ impl Camera {
 
         Self::MATRIX_CORRECTION_FOR_WGPU * projection * view
     }
+
+    // Corners of the part of the view between two distances from the eye, near corners first
+    pub(crate) fn frustum_corners(&self, near: f32, far: f32) -> [Point3<f32>; 8] {
+        let forward = (self.target - self.eye).normalize();
+        let right = forward.cross(Vector3::unit_y()).normalize();
+        let up = right.cross(forward);
+
+        let tan = (cgmath::Deg(Self::FOV / 2.0)).tan();
+        let corners = |distance: f32| {
+            let half_height = tan * distance;
+            let half_width = half_height * self.aspect;
+            let center = self.eye + forward * distance;
+
+            [
+                center - right * half_width - up * half_height,
+                center + right * half_width - up * half_height,
+                center + right * half_width + up * half_height,
+                center - right * half_width + up * half_height
+            ]
+        };
+
+        let (near, far) = (corners(near.max(Self::ZNEAR)), corners(far.min(Self::ZFAR)));
+        [near[0], near[1], near[2], near[3], far[0], far[1], far[2], far[3]]
+    }
 }
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
@@ -1,8 +1,26 @@
-pub(crate) const MAX_LIGHT_SOURCES: usize = 64;
+use cgmath::Point3;
+
+// Width of a cluster, in tiles
+const CLUSTER_SIZE: f32 = 4.0;
+
+// Clusters grow when a grid of the default size would have more than this many of them
+const MAX_CLUSTERS: usize = 1 << 16;
+
+// Brightness below which a light is ignored, used to find how far its light reaches
+// Light falls off with the fourth power of distance
+const LIGHT_CUTOFF: f32 = 1.0 / 256.0;
+
+// The most that any material in the world makes of a light, which decides how far lights have to reach
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct LightResponse {
+    pub(crate) ambient: f32,
+    pub(crate) specular_strength: f32
+}
 
 #[repr(C)]
 #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub(crate) struct Light {
+    // The radius of the light is stored in `w`
     pub(crate) position: [f32; 4],
     pub(crate) color: [f32; 4]
 }
@@ -13,8 +31,112 @@ impl Default for Light {
     }
 }
 
+impl Light {
+    pub(crate) fn new(position: Point3<f32>, color: [f32; 4], response: LightResponse) -> Self {
+        // The shader adds `color * (ambient * intensity + diffuse + specular) * intensity / distance⁴`,
+        // where diffuse is at most 1 and specular is at most the specular strength
+        let intensity = color[3].max(0.0);
+        let brightest = color[0].max(color[1]).max(color[2]).max(0.0)
+            * (response.ambient * intensity + 1.0 + response.specular_strength)
+            * intensity;
+        let radius = (brightest / LIGHT_CUTOFF).powf(0.25);
+
+        Self {
+            position: [position.x, position.y, position.z, radius],
+            color
+        }
+    }
+
+    pub(crate) fn radius(&self) -> f32 {
+        self.position[3]
+    }
+}
+
+// Describes the grid of clusters, with the width of a cluster stored in `origin.w`
 #[repr(C)]
 #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
-pub(crate) struct LightSources {
-    pub(crate) light_uniforms: [Light; MAX_LIGHT_SOURCES]
-}
\ No newline at end of file
+pub(crate) struct ClusterGrid {
+    pub(crate) origin: [f32; 4],
+    pub(crate) dimensions: [u32; 4]
+}
+
+// Splits the space around the lights into a grid of boxes, each of which lists the lights that reach it
+// Fragments only loop over the lights in their own cluster, so the number of lights isn't capped
+pub(crate) struct LightClusters {
+    pub(crate) grid: ClusterGrid,
+    // Offset into `indices` and number of lights, for each cluster
+    pub(crate) clusters: Vec<[u32; 2]>,
+    pub(crate) indices: Vec<u32>
+}
+
+impl LightClusters {
+    pub(crate) fn build(lights: &[Light]) -> Self {
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+
+        let lit = lights
+            .iter()
+            .enumerate()
+            .filter(|(_, light)| light.radius() > 0.0)
+            .collect::<Vec<_>>();
+
+        for (_, light) in lit.iter() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(light.position[axis] - light.radius());
+                max[axis] = max[axis].max(light.position[axis] + light.radius());
+            }
+        }
+
+        if lit.is_empty() {
+            return Self {
+                grid: ClusterGrid { origin: [0.0, 0.0, 0.0, CLUSTER_SIZE], dimensions: [0; 4] },
+                clusters: Vec::new(),
+                indices: Vec::new()
+            };
+        }
+
+        let mut size = CLUSTER_SIZE;
+        let dimensions = loop {
+            let dimensions = [0, 1, 2].map(|axis| ((max[axis] - min[axis]) / size).ceil().max(1.0) as usize);
+            if dimensions.iter().product::<usize>() <= MAX_CLUSTERS { break dimensions; }
+
+            size *= 2.0;
+        };
+
+        let cell = |axis: usize, value: f32| {
+            (((value - min[axis]) / size).floor().max(0.0) as usize).min(dimensions[axis] - 1)
+        };
+
+        // Lights are listed by the clusters that their bounding boxes overlap
+        let mut lists: Vec<Vec<u32>> = vec![Vec::new(); dimensions.iter().product()];
+        for (index, light) in lit {
+            let low = [0, 1, 2].map(|axis| cell(axis, light.position[axis] - light.radius()));
+            let high = [0, 1, 2].map(|axis| cell(axis, light.position[axis] + light.radius()));
+
+            for z in low[2]..=high[2] {
+                for y in low[1]..=high[1] {
+                    for x in low[0]..=high[0] {
+                        let cluster = x + dimensions[0] * (y + dimensions[1] * z);
+                        lists[cluster].push(index as u32);
+                    }
+                }
+            }
+        }
+
+        let mut clusters = Vec::with_capacity(lists.len());
+        let mut indices = Vec::new();
+        for mut list in lists {
+            clusters.push([indices.len() as u32, list.len() as u32]);
+            indices.append(&mut list);
+        }
+
+        Self {
+            grid: ClusterGrid {
+                origin: [min[0], min[1], min[2], size],
+                dimensions: [dimensions[0] as u32, dimensions[1] as u32, dimensions[2] as u32, 0]
+            },
+            clusters,
+            indices
+        }
+    }
+}
@@ -1,11 +1,17 @@
 mod state;
 mod light;
+mod buffer;
+mod shadow;
 
 mod vertex;
 pub use vertex::Vertex;
 
 pub mod camera;
 pub mod world;
+pub mod navigation;
+pub mod steering;
+pub mod behaviour;
+pub mod texture;
 
 use std::time;
 
@@ -27,15 +33,15 @@ pub struct GameData<'a, 'b> {
     pub camera: &'a mut camera::Camera,
 }
 
-pub async fn run<I: 'static, U: 'static, E: 'static>(
+pub async fn run<I, U, E>(
     config: Config, 
     game_init: I,
     mut game_update: U, 
     mut process_events: E
 ) where
-    I: FnOnce(GameData),
-    U: FnMut(GameData), 
-    E: FnMut(GameWindow, GameEvent, GameData) -> bool {
+    I: FnOnce(GameData) + 'static,
+    U: FnMut(GameData) + 'static, 
+    E: FnMut(GameWindow, GameEvent, GameData) -> bool + 'static {
 
     // Initialize the Window and EventLoop
     let event_loop = event_loop::EventLoop::new();
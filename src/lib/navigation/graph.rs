@@ -0,0 +1,401 @@
+use std::{
+    cmp,
+    collections::{ BinaryHeap, HashMap, HashSet }
+};
+
+use cgmath::{
+    Point3,
+    Vector3,
+    MetricSpace
+};
+
+use crate::world::World;
+
+use super::NavigationConfig;
+
+// Regions are never longer than this along either horizontal axis,
+// so that the distance between their centers stays a fair estimate of the cost of crossing them
+const MAX_REGION_SIZE: i16 = 8;
+
+// Half of the width of a walker, which must fit along every smoothed segment of a path
+const CORRIDOR_HALF_WIDTH: f32 = 0.3;
+
+// Distance between the points checked along a smoothed segment
+const CORRIDOR_SAMPLE_STEP: f32 = 0.1;
+
+// Connection between cells in neighbouring regions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Portal {
+    pub region: usize,
+    pub from: Point3<i16>,
+    pub to: Point3<i16>,
+    pub cost: f32
+}
+
+// Rectangle of walkable cells at the same height
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub min: Point3<i16>,
+    pub max: Point3<i16>,
+    pub portals: Vec<Portal>
+}
+
+impl Region {
+    pub fn center(&self) -> Point3<f32> {
+        Point3::new(
+            (self.min.x + self.max.x) as f32 * 0.5,
+            self.min.y as f32,
+            (self.min.z + self.max.z) as f32 * 0.5
+        )
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = Point3<i16>> + '_ {
+        (self.min.x..=self.max.x).flat_map(move |x| {
+            (self.min.z..=self.max.z).map(move |z| Point3::new(x, self.min.y, z))
+        } )
+    }
+
+    fn intersects(&self, min: Point3<i16>, max: Point3<i16>) -> bool {
+        self.min.x <= max.x && self.max.x >= min.x &&
+        self.min.y <= max.y && self.max.y >= min.y &&
+        self.min.z <= max.z && self.max.z >= min.z
+    }
+}
+
+// Region waiting to be expanded, ordered so that the cheapest estimate is popped first
+#[derive(Clone, Copy, PartialEq)]
+struct OpenRegion {
+    estimate: f32,
+    region: usize
+}
+
+impl Eq for OpenRegion {  }
+
+impl Ord for OpenRegion {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for OpenRegion {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Walkable surfaces of a world, merged into regions
+// Only the regions around tiles that changed are rebuilt when the graph is updated
+pub struct NavigationGraph {
+    config: NavigationConfig,
+    revision: usize,
+    regions: HashMap<usize, Region>,
+    cell_regions: HashMap<Point3<i16>, usize>,
+    next_region: usize
+}
+
+impl NavigationGraph {
+    pub fn new(world: &World, config: NavigationConfig) -> Self {
+        let mut graph = Self {
+            config,
+            revision: world.tile_revision(),
+            regions: HashMap::new(),
+            cell_regions: HashMap::new(),
+            next_region: 0
+        };
+
+        let cells = world
+            .tile_positions()
+            .map(|position| position + Vector3::unit_y())
+            .filter(|cell| super::is_walkable(world, *cell, &config))
+            .collect::<HashSet<_>>();
+
+        let created = graph.merge_regions(cells);
+        graph.connect_regions(world, &created);
+
+        graph
+    }
+
+    pub fn config(&self) -> &NavigationConfig {
+        &self.config
+    }
+
+    pub fn regions(&self) -> impl Iterator<Item = &Region> {
+        self.regions.values()
+    }
+
+    pub fn region_at(&self, cell: Point3<i16>) -> Option<&Region> {
+        self.cell_regions
+            .get(&cell)
+            .map(|region| &self.regions[region])
+    }
+
+    // Rebuilds the regions around the tiles that were added or removed since the last update
+    // The whole graph is rebuilt if the world no longer remembers all of the changes
+    pub fn update(&mut self, world: &World) {
+        let changes = match world.tile_changes_since(self.revision) {
+            Some(changes) => changes.collect::<Vec<_>>(),
+            None => {
+                *self = Self::new(world, self.config);
+                return;
+            }
+        };
+        self.revision = world.tile_revision();
+
+        for position in changes {
+            self.invalidate(world, position);
+        }
+    }
+
+    fn invalidate(&mut self, world: &World, position: Point3<i16>) {
+        let config = self.config;
+
+        // Cells whose walkability depends on the tile
+        let affected_min = position - Vector3::new(1, config.clearance.max(1), 1);
+        let affected_max = position + Vector3::new(1, 1, 1);
+
+        let dissolved = self.regions
+            .iter()
+            .filter(|(_, region)| region.intersects(affected_min, affected_max))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        let mut cells = HashSet::new();
+        for id in dissolved.iter() {
+            let region = self.regions.remove(id).unwrap();
+            for cell in region.cells() {
+                self.cell_regions.remove(&cell);
+                cells.insert(cell);
+            }
+        }
+
+        for x in affected_min.x..=affected_max.x {
+            for y in affected_min.y..=affected_max.y {
+                for z in affected_min.z..=affected_max.z {
+                    cells.insert(Point3::new(x, y, z));
+                }
+            }
+        }
+
+        cells.retain(|cell| super::is_walkable(world, *cell, &config));
+        let mut reconnected = self.merge_regions(cells);
+
+        // Portals that climb or drop into the changed area start further away
+        let portal_min = affected_min - Vector3::new(1, config.max_climb.max(0), 1);
+        let portal_max = affected_max + Vector3::new(1, config.max_drop.max(0), 1);
+
+        for (id, region) in self.regions.iter_mut() {
+            let leads_to_dissolved = region.portals.iter().any(|portal| dissolved.contains(&portal.region));
+            if leads_to_dissolved || region.intersects(portal_min, portal_max) {
+                region.portals.clear();
+                if !reconnected.contains(id) {
+                    reconnected.push(*id);
+                }
+            }
+        }
+
+        self.connect_regions(world, &reconnected);
+    }
+
+    // Greedily merges cells into rectangles, returning the new regions
+    fn merge_regions(&mut self, cells: HashSet<Point3<i16>>) -> Vec<usize> {
+        let mut sorted = cells.iter().copied().collect::<Vec<_>>();
+        sorted.sort_by_key(|cell| (cell.y, cell.z, cell.x));
+
+        let mut assigned = HashSet::new();
+        let mut created = Vec::new();
+
+        let is_free = |cell: Point3<i16>, assigned: &HashSet<Point3<i16>>| {
+            cells.contains(&cell) && !assigned.contains(&cell)
+        };
+
+        for start in sorted {
+            if assigned.contains(&start) { continue; }
+
+            let mut max = start;
+            while max.x - start.x + 1 < MAX_REGION_SIZE &&
+                is_free(Point3::new(max.x + 1, start.y, start.z), &assigned) {
+                max.x += 1;
+            }
+
+            while max.z - start.z + 1 < MAX_REGION_SIZE &&
+                (start.x..=max.x).all(|x| is_free(Point3::new(x, start.y, max.z + 1), &assigned)) {
+                max.z += 1;
+            }
+
+            let region = Region { min: start, max, portals: Vec::new() };
+            for cell in region.cells() {
+                assigned.insert(cell);
+                self.cell_regions.insert(cell, self.next_region);
+            }
+
+            self.regions.insert(self.next_region, region);
+            created.push(self.next_region);
+            self.next_region += 1;
+        }
+
+        created
+    }
+
+    // Finds the portals leading out of each of the given regions
+    fn connect_regions(&mut self, world: &World, ids: &[usize]) {
+        for id in ids {
+            let mut portals = Vec::new();
+
+            for cell in self.regions[id].cells() {
+                for (neighbour, cost) in super::neighbours(world, cell, &self.config) {
+                    match self.cell_regions.get(&neighbour) {
+                        Some(region) if region != id => portals.push(Portal {
+                            region: *region,
+                            from: cell,
+                            to: neighbour,
+                            cost
+                        } ),
+                        _ => {  }
+                    }
+                }
+            }
+
+            self.regions.get_mut(id).unwrap().portals = portals;
+        }
+    }
+
+    // Finds a smoothed path between two points, updating the graph first if the world changed
+    pub fn find_path(
+        &mut self,
+        world: &World,
+        start: Point3<f32>,
+        goal: Point3<f32>
+    ) -> Option<Vec<Point3<f32>>> {
+        self.update(world);
+
+        let start_cell = super::ground_cell(world, start, &self.config)?;
+        let goal_cell = super::ground_cell(world, goal, &self.config)?;
+
+        let start_region = *self.cell_regions.get(&start_cell)?;
+        let goal_region = *self.cell_regions.get(&goal_cell)?;
+
+        let regions = self.find_region_path(start_region, goal_region)?;
+
+        // Place the ends of the path on the ground
+        let start = Point3::new(start.x, start_cell.y as f32, start.z);
+        let goal = Point3::new(goal.x, goal_cell.y as f32, goal.z);
+
+        // Cross between each pair of regions through the portal that keeps the path shortest
+        let mut path = vec![start];
+        for (index, pair) in regions.windows(2).enumerate() {
+            let previous = *path.last().unwrap();
+            let next = match regions.get(index + 2) {
+                Some(region) => self.regions[region].center(),
+                None => goal
+            };
+
+            let portal = self.regions[&pair[0]].portals
+                .iter()
+                .filter(|portal| portal.region == pair[1])
+                .min_by(|a, b| {
+                    let length = |portal: &Portal| {
+                        previous.distance(super::cell_center(portal.from)) +
+                            super::cell_center(portal.to).distance(next)
+                    };
+
+                    length(a).total_cmp(&length(b))
+                } )?;
+
+            path.push(super::cell_center(portal.from));
+            path.push(super::cell_center(portal.to));
+        }
+
+        path.push(goal);
+        path.dedup();
+
+        Some(self.pull_string(&path))
+    }
+
+    // A* over regions, returning the regions passed through from the start to the goal
+    fn find_region_path(&self, start: usize, goal: usize) -> Option<Vec<usize>> {
+        let goal_center = self.regions[&goal].center();
+
+        let mut open = BinaryHeap::new();
+        let mut costs = HashMap::new();
+        let mut came_from = HashMap::new();
+
+        costs.insert(start, 0.0);
+        open.push(OpenRegion { estimate: self.regions[&start].center().distance(goal_center), region: start } );
+
+        while let Some(OpenRegion { region, .. }) = open.pop() {
+            if region == goal {
+                let mut path = vec![goal];
+                while let Some(previous) = came_from.get(path.last().unwrap()) {
+                    path.push(*previous);
+                }
+
+                path.reverse();
+                return Some(path);
+            }
+
+            let center = self.regions[&region].center();
+            let cost = costs[&region];
+
+            for portal in self.regions[&region].portals.iter() {
+                let neighbour_center = self.regions[&portal.region].center();
+
+                // Climbs and drops cost more than the distance they cover
+                let neighbour_cost = cost + center.distance(neighbour_center) + portal.cost - 1.0;
+
+                if costs.get(&portal.region).is_none_or(|previous| neighbour_cost < *previous) {
+                    costs.insert(portal.region, neighbour_cost);
+                    came_from.insert(portal.region, region);
+                    open.push(OpenRegion {
+                        estimate: neighbour_cost + neighbour_center.distance(goal_center),
+                        region: portal.region
+                    } );
+                }
+            }
+        }
+
+        None
+    }
+
+    // Removes waypoints that can be skipped by walking in a straight line
+    fn pull_string(&self, path: &[Point3<f32>]) -> Vec<Point3<f32>> {
+        let mut pulled = vec![path[0]];
+
+        let mut anchor = 0;
+        while anchor < path.len() - 1 {
+            let mut next = anchor + 1;
+            for candidate in (anchor + 2..path.len()).rev() {
+                if self.is_corridor_walkable(path[anchor], path[candidate]) {
+                    next = candidate;
+                    break;
+                }
+            }
+
+            pulled.push(path[next]);
+            anchor = next;
+        }
+
+        pulled
+    }
+
+    // Whether a walker can move in a straight line between two points at the same height
+    fn is_corridor_walkable(&self, from: Point3<f32>, to: Point3<f32>) -> bool {
+        if from.y != to.y { return false; }
+
+        let samples = (from.distance(to) / CORRIDOR_SAMPLE_STEP).ceil().max(1.0) as usize;
+        let y = from.y.round() as i16;
+
+        (0..=samples).all(|sample| {
+            let point = from + (to - from) * (sample as f32 / samples as f32);
+
+            [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)].iter().all(|(dx, dz)| {
+                let cell = Point3::new(
+                    (point.x + dx * CORRIDOR_HALF_WIDTH).round() as i16,
+                    y,
+                    (point.z + dz * CORRIDOR_HALF_WIDTH).round() as i16
+                );
+
+                self.cell_regions.contains_key(&cell)
+            } )
+        } )
+    }
+}
@@ -0,0 +1,104 @@
+pub(crate) mod search;
+pub use search::{ PathSearch, SearchState, find_path };
+
+pub(crate) mod graph;
+pub use graph::{ NavigationGraph, Region, Portal };
+
+use cgmath::{
+    Point3,
+    Vector3
+};
+
+use crate::world::World;
+
+// Horizontal directions that a walker can move in between cells
+const DIRECTIONS: [(i16, i16); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+// Describes what a walker is able to traverse
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NavigationConfig {
+    // Highest ledge, in tiles, that can be climbed onto from a neighbouring cell
+    pub max_climb: i16,
+    // Furthest distance, in tiles, that can be dropped down from a neighbouring cell
+    pub max_drop: i16,
+    // Number of empty cells needed above the ground
+    pub clearance: i16,
+    // Searches give up after expanding this many cells
+    pub max_nodes: usize
+}
+
+impl Default for NavigationConfig {
+    fn default() -> Self {
+        Self {
+            max_climb: 1,
+            max_drop: 3,
+            clearance: 1,
+            max_nodes: 4096
+        }
+    }
+}
+
+// A cell is walkable if it has solid ground beneath it, and enough room above it
+pub fn is_walkable(world: &World, cell: Point3<i16>, config: &NavigationConfig) -> bool {
+    world.is_solid(cell - Vector3::unit_y()) &&
+        (0..config.clearance.max(1)).all(|offset| !world.is_solid(cell + Vector3::unit_y() * offset))
+}
+
+// The walkable cell that a point is standing in, or above
+// Looks at most `max_drop` cells down, so airborne entities still have a position on the grid
+pub fn ground_cell(world: &World, point: Point3<f32>, config: &NavigationConfig) -> Option<Point3<i16>> {
+    let cell = Point3::new(point.x.round() as i16, point.y.round() as i16, point.z.round() as i16);
+
+    (0..=config.max_drop.max(0))
+        .map(|depth| cell - Vector3::unit_y() * depth)
+        .find(|cell| is_walkable(world, *cell, config))
+}
+
+// Walkable cells that can be reached from `cell` in a single move, along with the cost of each move
+// Climbing and dropping cost more than walking on level ground
+pub fn neighbours(
+    world: &World,
+    cell: Point3<i16>,
+    config: &NavigationConfig
+) -> Vec<(Point3<i16>, f32)> {
+    let mut neighbours = Vec::with_capacity(DIRECTIONS.len());
+    let is_clear = |cell: Point3<i16>| !world.is_solid(cell);
+    let has_room = |cell: Point3<i16>| {
+        (0..config.clearance.max(1)).all(|offset| is_clear(cell + Vector3::unit_y() * offset))
+    };
+
+    for (dx, dz) in DIRECTIONS {
+        let column = Point3::new(cell.x + dx, cell.y, cell.z + dz);
+
+        // Climbing needs headroom above the walker before it moves across
+        for height in 1..=config.max_climb.max(0) {
+            let above = cell + Vector3::unit_y() * (height + config.clearance.max(1) - 1);
+            if !is_clear(above) { break; }
+
+            let target = column + Vector3::unit_y() * height;
+            if is_walkable(world, target, config) {
+                neighbours.push((target, 1.0 + height as f32 * 0.5));
+            }
+        }
+
+        if !has_room(column) { continue; }
+
+        // Walk across, or fall until landing on the ground
+        for depth in 0..=config.max_drop.max(0) {
+            let target = column - Vector3::unit_y() * depth;
+            if !is_clear(target) { break; }
+
+            if is_walkable(world, target, config) {
+                neighbours.push((target, 1.0 + depth as f32 * 0.5));
+                break;
+            }
+        }
+    }
+
+    neighbours
+}
+
+// The point that a walker aims for when moving towards a cell
+pub fn cell_center(cell: Point3<i16>) -> Point3<f32> {
+    cell.cast::<f32>().unwrap()
+}
@@ -0,0 +1,171 @@
+use std::{
+    cmp,
+    collections::{ BinaryHeap, HashMap }
+};
+
+use cgmath::Point3;
+
+use crate::world::World;
+
+use super::NavigationConfig;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchState {
+    // The search needs more steps before it finishes
+    Searching,
+    // Waypoints from the start to the goal, including both
+    Found(Vec<Point3<f32>>),
+    // The goal can't be reached, or the search expanded too many cells
+    NotFound
+}
+
+// Cell waiting to be expanded, ordered so that the cheapest estimate is popped first
+#[derive(Clone, Copy, PartialEq)]
+struct OpenCell {
+    estimate: f32,
+    cell: Point3<i16>
+}
+
+impl Eq for OpenCell {  }
+
+impl Ord for OpenCell {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// A* search over walkable cells, which can be spread across several ticks
+pub struct PathSearch {
+    config: NavigationConfig,
+    goal: Point3<i16>,
+    open: BinaryHeap<OpenCell>,
+    costs: HashMap<Point3<i16>, f32>,
+    came_from: HashMap<Point3<i16>, Point3<i16>>,
+    expanded: usize,
+    state: SearchState
+}
+
+// Never overestimates the cost of reaching the goal, since every move costs
+// at least 1 per tile travelled horizontally and 0.5 per tile travelled vertically
+fn heuristic(from: Point3<i16>, to: Point3<i16>) -> f32 {
+    // Positions are widened first, since tiles far apart are further than an i16 can hold
+    let horizontal = (from.x as i32 - to.x as i32).abs() + (from.z as i32 - to.z as i32).abs();
+    let vertical = (from.y as i32 - to.y as i32).abs();
+
+    horizontal as f32 + vertical as f32 * 0.5
+}
+
+impl PathSearch {
+    // Starts a search between the cells beneath the two points
+    pub fn new(
+        world: &World,
+        start: Point3<f32>,
+        goal: Point3<f32>,
+        config: NavigationConfig
+    ) -> Self {
+        let mut search = Self {
+            config,
+            goal: Point3::new(0, 0, 0),
+            open: BinaryHeap::new(),
+            costs: HashMap::new(),
+            came_from: HashMap::new(),
+            expanded: 0,
+            state: SearchState::Searching
+        };
+
+        match (super::ground_cell(world, start, &config), super::ground_cell(world, goal, &config)) {
+            (Some(start), Some(goal)) => {
+                search.goal = goal;
+                search.costs.insert(start, 0.0);
+                search.open.push(OpenCell { estimate: heuristic(start, goal), cell: start } );
+            },
+            _ => search.state = SearchState::NotFound
+        }
+
+        search
+    }
+
+    pub fn state(&self) -> &SearchState {
+        &self.state
+    }
+
+    // Expands at most `budget` cells, so that long searches can be spread across ticks
+    // The world should not change between steps of the same search
+    pub fn step(&mut self, world: &World, budget: usize) -> &SearchState {
+        for _ in 0..budget {
+            if self.state != SearchState::Searching { break; }
+
+            let OpenCell { cell, .. } = match self.open.pop() {
+                Some(open) => open,
+                None => {
+                    self.state = SearchState::NotFound;
+                    break;
+                }
+            };
+
+            if cell == self.goal {
+                self.state = SearchState::Found(self.build_path());
+                break;
+            }
+
+            self.expanded += 1;
+            if self.expanded > self.config.max_nodes {
+                self.state = SearchState::NotFound;
+                break;
+            }
+
+            let cost = self.costs[&cell];
+            for (neighbour, move_cost) in super::neighbours(world, cell, &self.config) {
+                let neighbour_cost = cost + move_cost;
+
+                if self.costs.get(&neighbour).is_none_or(|previous| neighbour_cost < *previous) {
+                    self.costs.insert(neighbour, neighbour_cost);
+                    self.came_from.insert(neighbour, cell);
+                    self.open.push(OpenCell {
+                        estimate: neighbour_cost + heuristic(neighbour, self.goal),
+                        cell: neighbour
+                    } );
+                }
+            }
+        }
+
+        &self.state
+    }
+
+    // Runs the search until it finishes
+    pub fn finish(&mut self, world: &World) -> &SearchState {
+        self.step(world, usize::MAX)
+    }
+
+    fn build_path(&self) -> Vec<Point3<f32>> {
+        let mut path = vec![super::cell_center(self.goal)];
+
+        let mut cell = self.goal;
+        while let Some(previous) = self.came_from.get(&cell) {
+            path.push(super::cell_center(*previous));
+            cell = *previous;
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+// Finds a path between two points in a single call
+pub fn find_path(
+    world: &World,
+    start: Point3<f32>,
+    goal: Point3<f32>,
+    config: NavigationConfig
+) -> Option<Vec<Point3<f32>>> {
+    match PathSearch::new(world, start, goal, config).finish(world) {
+        SearchState::Found(path) => Some(path.clone()),
+        _ => None
+    }
+}
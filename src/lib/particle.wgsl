@@ -0,0 +1,45 @@
+struct CameraUniform {
+    position: vec4<f32>,
+    projection: mat4x4<f32>
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct VertexInput {
+    @location(0) center: vec3<f32>,
+    @location(1) corner: vec2<f32>,
+    @location(2) size: f32,
+    @location(3) color: vec3<f32>
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>
+};
+
+@vertex
+fn vs_main(model: VertexInput) -> VertexOutput {
+    // Turn the quad to face the camera
+    let to_camera = normalize(camera.position.xyz - model.center);
+    var right = cross(vec3<f32>(0.0, 1.0, 0.0), to_camera);
+    if(length(right) < 0.001) {
+        right = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    right = normalize(right);
+    let up = cross(to_camera, right);
+
+    let position = model.center + (right * model.corner.x + up * model.corner.y) * model.size;
+
+    var out: VertexOutput;
+    out.clip_position = camera.projection * vec4<f32>(position, 1.0);
+    out.color = model.color;
+
+    return out;
+}
+
+// Particles are unlit, so they glow in the dark
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
@@ -15,58 +15,311 @@ struct LightSources {
     light_uniforms: array<LightUniform>
 }
 
+// Width of a cluster is stored in `origin.w`
+struct ClusterGrid {
+    origin: vec4<f32>,
+    dimensions: vec4<u32>
+}
+
+// Offset into the light indices and number of lights, for each cluster
+struct Clusters {
+    clusters: array<vec2<u32>>
+}
+
+struct LightIndices {
+    indices: array<u32>
+}
+
 @group(1) @binding(0) 
 var<storage, read> light_sources: LightSources;
 
+@group(1) @binding(1) 
+var<uniform> cluster_grid: ClusterGrid;
+
+@group(1) @binding(2) 
+var<storage, read> clusters: Clusters;
+
+@group(1) @binding(3) 
+var<storage, read> light_indices: LightIndices;
+
+struct Material {
+    emissive: vec3<f32>,
+    shininess: f32,
+    specular_strength: f32,
+    ambient: f32
+}
+
+// Looked up by the index in each vertex or instance, with the default material first
+struct Materials {
+    materials: array<Material>
+}
+
+@group(1) @binding(4) 
+var<storage, read> materials: Materials;
+
+// The fraction of ambient light is stored in `direction.w`, and the intensity in `color.a`
+struct SunUniform {
+    direction: vec4<f32>,
+    color: vec4<f32>,
+    cascades: array<mat4x4<f32>, 3>
+}
+
+@group(2) @binding(0)
+var<uniform> sun: SunUniform;
+
+@group(2) @binding(1)
+var sun_shadow_map: texture_depth_2d_array;
+
+// Each light with a shadow has six layers in the shadow map, one for each face of its cube
+// Unused entries of `lights` are -1
+struct PointShadowUniform {
+    faces: array<mat4x4<f32>, 24>,
+    lights: vec4<i32>
+}
+
+@group(2) @binding(2)
+var<uniform> point_shadows: PointShadowUniform;
+
+@group(2) @binding(3)
+var point_shadow_map: texture_depth_2d_array;
+
+// Shared by both shadow maps
+@group(2) @binding(4)
+var shadow_sampler: sampler_comparison;
+
+// Every texture in the world, packed into one atlas with a matching atlas of normal maps
+@group(3) @binding(0)
+var atlas_color: texture_2d<f32>;
+
+@group(3) @binding(1)
+var atlas_normal: texture_2d<f32>;
+
+@group(3) @binding(2)
+var atlas_sampler: sampler;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
-    @location(1) color: vec3<f32>,
-    @location(2) normal: vec3<f32>
+    @location(1) color: vec4<f32>,
+    @location(2) normal: vec3<f32>,
+    // Voxel light, with the ambient occlusion in `w`
+    @location(3) light: vec4<f32>,
+    @location(4) uv: vec2<f32>,
+    // Material index, with the emissive color packed into `y`
+    @location(5) material: vec2<u32>
+};
+
+struct InstanceInput {
+    @location(6) model_0: vec4<f32>,
+    @location(7) model_1: vec4<f32>,
+    @location(8) model_2: vec4<f32>,
+    @location(9) model_3: vec4<f32>,
+    @location(10) normal_0: vec3<f32>,
+    @location(11) normal_1: vec3<f32>,
+    @location(12) normal_2: vec3<f32>,
+    @location(13) color: vec4<f32>,
+    @location(14) light: vec3<f32>,
+    @location(15) material: vec2<u32>
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec3<f32>,
+    @location(0) color: vec4<f32>,
     @location(1) world_normal: vec3<f32>,
-    @location(2) world_position: vec3<f32>
+    @location(2) world_position: vec3<f32>,
+    @location(3) voxel_light: vec3<f32>,
+    @location(4) ao: f32,
+    @location(5) uv: vec2<f32>,
+    @location(6) @interpolate(flat) material: u32,
+    @location(7) emissive: vec3<f32>
 };
 
+// Unpacks a color with 8 bits per channel, with red in the lowest byte
+fn unpack_color(color: u32) -> vec3<f32> {
+    return vec3<f32>(
+        f32(color & 255u),
+        f32((color >> 8u) & 255u),
+        f32((color >> 16u) & 255u)
+    ) / 255.0;
+}
+
 @vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
+fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let model_matrix = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    let normal_matrix = mat3x3<f32>(instance.normal_0, instance.normal_1, instance.normal_2);
+    let world_position = model_matrix * vec4<f32>(model.position, 1.0);
+
     var out: VertexOutput;
-    out.clip_position = camera.projection * vec4<f32>(model.position, 1.0);
-    out.color = model.color;
-    out.world_normal = model.normal;
-    out.world_position = model.position;
+    out.clip_position = camera.projection * world_position;
+    out.color = model.color * instance.color;
+    out.world_normal = normalize(normal_matrix * model.normal);
+    out.world_position = world_position.xyz;
+    // Tiles have light baked into their vertices, while meshes are lit by the light at their entity
+    out.voxel_light = model.light.xyz + instance.light;
+    out.ao = model.light.w;
+    out.uv = model.uv;
+    // Tiles have their material in their vertices, while meshes use the material of their entity
+    out.material = model.material.x + instance.material.x;
+    out.emissive = unpack_color(model.material.y) + unpack_color(instance.material.y);
     
     return out;
 }
 
+// Bends a surface normal by a tangent-space normal from the normal map
+// The tangent frame comes from how the texture coordinates change across the screen, so vertices don't need tangents
+// The derivatives are taken by the caller, since the GL backend also compiles this into the vertex shader
+fn perturb_normal(normal: vec3<f32>, dp1: vec3<f32>, dp2: vec3<f32>, duv1: vec2<f32>, duv2: vec2<f32>, mapped: vec3<f32>) -> vec3<f32> {
+    let dp2perp = cross(dp2, normal);
+    let dp1perp = cross(normal, dp1);
+    let tangent = dp2perp * duv1.x + dp1perp * duv2.x;
+    let bitangent = dp2perp * duv1.y + dp1perp * duv2.y;
+
+    // Degenerate frames, like those of untextured surfaces, leave the normal alone
+    let scale = max(dot(tangent, tangent), dot(bitangent, bitangent));
+    if(scale <= 0.0) {
+        return normal;
+    }
+
+    // Texture v runs down the image, while the normal map's y points up it
+    let inverse_scale = inverseSqrt(scale);
+    let frame = mat3x3<f32>(tangent * inverse_scale, -bitangent * inverse_scale, normal);
+    return normalize(frame * mapped);
+}
+
+// Fraction of the sun's light that reaches a position, filtered over the neighbouring texels
+fn sun_visibility(world_position: vec3<f32>) -> f32 {
+    let texel = 1.0 / f32(textureDimensions(sun_shadow_map).x);
+
+    // The nearest cascade that contains the position is used
+    for(var cascade: i32 = 0; cascade < 3; cascade = cascade + 1) {
+        let clip = sun.cascades[cascade] * vec4<f32>(world_position, 1.0);
+        let uv = clip.xy * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5, 0.5);
+
+        if(all(uv > vec2<f32>(texel)) && all(uv < vec2<f32>(1.0 - texel)) && clip.z <= 1.0) {
+            var visibility = 0.0;
+            for(var x: i32 = -1; x <= 1; x = x + 1) {
+                for(var y: i32 = -1; y <= 1; y = y + 1) {
+                    let offset = vec2<f32>(f32(x), f32(y)) * texel;
+                    visibility += textureSampleCompareLevel(sun_shadow_map, shadow_sampler, uv + offset, cascade, clip.z);
+                }
+            }
+
+            return visibility / 9.0;
+        }
+    }
+
+    return 1.0;
+}
+
+// Fraction of a point light that reaches a position, which is always 1 for lights without a shadow
+fn point_visibility(light: u32, light_position: vec3<f32>, world_position: vec3<f32>) -> f32 {
+    for(var slot: i32 = 0; slot < 4; slot = slot + 1) {
+        if(point_shadows.lights[slot] == i32(light)) {
+            // The face of the cube is picked by the axis that the position is furthest along
+            let offset = world_position - light_position;
+            let size = abs(offset);
+
+            var face = 0;
+            if(size.x >= size.y && size.x >= size.z) {
+                face = select(1, 0, offset.x > 0.0);
+            } else if(size.y >= size.z) {
+                face = select(3, 2, offset.y > 0.0);
+            } else {
+                face = select(5, 4, offset.z > 0.0);
+            }
+
+            let layer = slot * 6 + face;
+            let clip = point_shadows.faces[layer] * vec4<f32>(world_position, 1.0);
+            let depth = clip.z / clip.w;
+
+            // Positions inside the near plane are on the light's own tile or entity
+            if(depth < 0.0) {
+                return 1.0;
+            }
+
+            let uv = clip.xy / clip.w * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5, 0.5);
+            return textureSampleCompareLevel(point_shadow_map, shadow_sampler, uv, layer, depth);
+        }
+    }
+
+    return 1.0;
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     var result = vec3<f32>(0.0, 0.0, 0.0);
 
-    let length = i32(arrayLength(&light_sources.light_uniforms));
-    for(var i: i32 = 0; i < length; i = i + 1) {
-        if(light_sources.light_uniforms[i].color.a != 0.0) {
-            let ambient_color = light_sources.light_uniforms[i].color.xyz * light_sources.light_uniforms[i].color.a;
-            let light_dir = normalize(light_sources.light_uniforms[i].position.xyz - in.world_position);
-            let diffuse_strength = max(dot(in.world_normal, light_dir), 0.0);
-            let diffuse_color = light_sources.light_uniforms[i].color.xyz * diffuse_strength;
-            let view_dir = normalize(camera.position.xyz - in.world_position);
-            let reflect_dir = reflect(-light_dir, in.world_normal);
-            let specular_strength = pow(max(dot(view_dir, reflect_dir), 0.0), 32.0);
-            let specular_color = light_sources.light_uniforms[i].color.xyz * specular_strength;
-
-            let dist_sq: f32 = pow(distance(light_sources.light_uniforms[i].position.xyz, in.world_position), 4.0);
-
-            result += (ambient_color + diffuse_color + specular_color) / dist_sq * light_sources.light_uniforms[i].color.a;
-            
+    // The atlas is always sampled, so that its derivatives are taken in uniform control flow
+    let textured = in.uv.x >= 0.0;
+    let texture_color = textureSample(atlas_color, atlas_sampler, in.uv);
+    let mapped_normal = textureSample(atlas_normal, atlas_sampler, in.uv).xyz * 2.0 - 1.0;
+    let bumped_normal = perturb_normal(
+        normalize(in.world_normal),
+        dpdx(in.world_position),
+        dpdy(in.world_position),
+        dpdx(in.uv),
+        dpdy(in.uv),
+        mapped_normal
+    );
+
+    // Alpha only matters in the transparent pass, since the opaque pass replaces what is behind
+    let color = in.color * select(vec4<f32>(1.0), texture_color, textured);
+    let albedo = color.rgb;
+    let normal = select(in.world_normal, bumped_normal, textured);
+
+    let material = materials.materials[in.material];
+    let view_dir = normalize(camera.position.xyz - in.world_position);
+
+    // Only the lights that reach the fragment's cluster are considered
+    let cell = vec3<i32>(floor((in.world_position - cluster_grid.origin.xyz) / cluster_grid.origin.w));
+    let dimensions = vec3<i32>(cluster_grid.dimensions.xyz);
+
+    if(all(cell >= vec3<i32>(0)) && all(cell < dimensions)) {
+        let cluster = clusters.clusters[cell.x + dimensions.x * (cell.y + dimensions.y * cell.z)];
+
+        for(var j: u32 = 0u; j < cluster.y; j = j + 1u) {
+            let i = light_indices.indices[cluster.x + j];
+            let light_distance = distance(light_sources.light_uniforms[i].position.xyz, in.world_position);
+
+            if(light_distance < light_sources.light_uniforms[i].position.w) {
+                let ambient_color = light_sources.light_uniforms[i].color.xyz * light_sources.light_uniforms[i].color.a * material.ambient;
+                let light_dir = normalize(light_sources.light_uniforms[i].position.xyz - in.world_position);
+                let diffuse_strength = max(dot(normal, light_dir), 0.0);
+                let diffuse_color = light_sources.light_uniforms[i].color.xyz * diffuse_strength;
+                let reflect_dir = reflect(-light_dir, normal);
+                let specular_strength = pow(max(dot(view_dir, reflect_dir), 0.0), material.shininess) * material.specular_strength;
+                let specular_color = light_sources.light_uniforms[i].color.xyz * specular_strength;
+
+                let dist_sq: f32 = pow(light_distance, 4.0);
+                let visibility = point_visibility(i, light_sources.light_uniforms[i].position.xyz, in.world_position + in.world_normal * 0.02);
+
+                result += (ambient_color + diffuse_color + specular_color) / dist_sq * light_sources.light_uniforms[i].color.a * visibility;
+            }
         }
     }
-    
 
-    result *= in.color;
+    if(sun.color.a != 0.0) {
+        let sun_dir = -normalize(sun.direction.xyz);
+        let diffuse_strength = max(dot(normal, sun_dir), 0.0);
+
+        // Surfaces facing away from the sun are already dark, so their shadows aren't looked up
+        var visibility = 0.0;
+        if(diffuse_strength > 0.0) {
+            visibility = sun_visibility(in.world_position + in.world_normal * 0.02);
+        }
+
+        let reflect_dir = reflect(-sun_dir, normal);
+        let specular_strength = pow(max(dot(view_dir, reflect_dir), 0.0), material.shininess) * material.specular_strength;
+
+        let ambient = sun.direction.w;
+        let direct = (diffuse_strength + specular_strength) * visibility;
+        result += sun.color.xyz * sun.color.a * (ambient * material.ambient + (1.0 - ambient) * direct);
+    }
+
+    result += in.voxel_light;
+
+    // Corners that are hemmed in by tiles get less of every light, but not of the light that the surface gives off
+    result *= albedo * in.ao;
+    result += material.emissive + in.emissive;
 
-    return vec4<f32>(result, 1.0);
+    return vec4<f32>(result, color.a);
 }
\ No newline at end of file
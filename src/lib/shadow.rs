@@ -0,0 +1,540 @@
+use std::num::NonZeroU32;
+
+use cgmath::{
+    Point3,
+    Vector3,
+    Matrix4,
+    InnerSpace,
+    EuclideanSpace,
+    MetricSpace,
+    Transform,
+    SquareMatrix
+};
+
+use wgpu::util::DeviceExt;
+
+use crate::{
+    camera::Camera,
+    vertex::{ Vertex, Instance },
+    light::Light,
+    world::Sun
+};
+
+pub(crate) const CASCADE_COUNT: usize = 3;
+
+// Width and height of each cascade's shadow map, in texels
+const SHADOW_MAP_SIZE: u32 = 2048;
+
+// Far end of each cascade, as a fraction of the sun's shadow distance
+// Nearer cascades cover less of the world, so shadows close to the camera are sharper
+const CASCADE_SPLITS: [f32; CASCADE_COUNT] = [0.1, 0.3, 1.0];
+
+// Distance behind each cascade that is still drawn into its shadow map, so that tall casters aren't clipped
+const CASTER_MARGIN: f32 = 50.0;
+
+// Most point lights that can have shadows at once
+pub(crate) const MAX_POINT_SHADOWS: usize = 4;
+
+// Width and height of each face of a point light's cube map, in texels
+const POINT_SHADOW_MAP_SIZE: u32 = 512;
+
+// Geometry closer to a point light than this doesn't cast shadows
+// This leaves out the tile or entity that gives off the light, which would otherwise hide it
+const POINT_SHADOW_NEAR: f32 = 0.6;
+
+// Direction and up vector of each face of a cube map, in the order that the shader picks them
+const CUBE_FACES: [(Vector3<f32>, Vector3<f32>); 6] = [
+    (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, -1.0, 0.0)),
+    (Vector3::new(-1.0, 0.0, 0.0), Vector3::new(0.0, -1.0, 0.0)),
+    (Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 1.0)),
+    (Vector3::new(0.0, -1.0, 0.0), Vector3::new(0.0, 0.0, -1.0)),
+    (Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, -1.0, 0.0)),
+    (Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.0, -1.0, 0.0))
+];
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct SunUniform {
+    // The fraction of ambient light is stored in `w`
+    pub(crate) direction: [f32; 4],
+    // The sun is disabled when the intensity in `w` is zero
+    pub(crate) color: [f32; 4],
+    // Takes a world position to the shadow map of each cascade
+    pub(crate) cascades: [[[f32; 4]; 4]; CASCADE_COUNT]
+}
+
+impl SunUniform {
+    pub(crate) fn new(sun: Option<Sun>, camera: &Camera) -> Self {
+        let sun = match sun.filter(|sun| sun.direction.magnitude2() > 0.0) {
+            Some(sun) => sun,
+            None => return Self {
+                direction: [0.0, -1.0, 0.0, 0.0],
+                color: [0.0; 4],
+                cascades: [Matrix4::identity().into(); CASCADE_COUNT]
+            }
+        };
+
+        let direction = sun.direction.normalize();
+        let up = if direction.y.abs() > 0.99 { Vector3::unit_z() } else { Vector3::unit_y() };
+        let view = Matrix4::look_to_rh(Point3::origin(), direction, up);
+
+        let mut cascades = [[[0.0; 4]; 4]; CASCADE_COUNT];
+        let mut near = 0.0;
+        for (index, split) in CASCADE_SPLITS.iter().enumerate() {
+            let far = split * sun.shadow_distance;
+
+            // A bounding sphere keeps the size of the cascade the same as the camera turns
+            let corners = camera.frustum_corners(near, far);
+            let center = Point3::centroid(&corners);
+            let radius = corners
+                .iter()
+                .map(|corner| corner.distance(center))
+                .fold(0.0, f32::max);
+
+            // Moving the cascade in whole texels stops the edges of shadows from crawling as the camera moves
+            let texel = 2.0 * radius / SHADOW_MAP_SIZE as f32;
+            let mut center = view.transform_point(center);
+            center.x = (center.x / texel).round() * texel;
+            center.y = (center.y / texel).round() * texel;
+
+            let projection = cgmath::ortho(
+                center.x - radius,
+                center.x + radius,
+                center.y - radius,
+                center.y + radius,
+                -center.z - radius - CASTER_MARGIN,
+                -center.z + radius
+            );
+
+            cascades[index] = (Camera::MATRIX_CORRECTION_FOR_WGPU * projection * view).into();
+            near = far;
+        }
+
+        Self {
+            direction: [direction.x, direction.y, direction.z, sun.ambient],
+            color: sun.color,
+            cascades
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.color[3] != 0.0
+    }
+}
+
+// Cascaded shadow maps for the sun, which are drawn before the rest of the scene
+pub(crate) struct SunShadows {
+    pub(crate) uniform: SunUniform,
+    uniform_buffer: wgpu::Buffer,
+    passes: ShadowPasses,
+    pub(crate) pipeline: wgpu::RenderPipeline
+}
+
+impl SunShadows {
+    pub(crate) fn new(device: &wgpu::Device, camera: &Camera) -> Self {
+        let uniform = SunUniform::new(None, camera);
+
+        let uniform_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(&[uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
+            }
+        );
+
+        let passes = ShadowPasses::new(device, SHADOW_MAP_SIZE, CASCADE_COUNT);
+        let pipeline = create_depth_pipeline(
+            device,
+            &passes.bind_group_layout,
+            &device.create_shader_module(wgpu::include_wgsl!("shadow.wgsl"))
+        );
+
+        Self {
+            uniform,
+            uniform_buffer,
+            passes,
+            pipeline
+        }
+    }
+
+    pub(crate) fn update(&mut self, queue: &wgpu::Queue, sun: Option<Sun>, camera: &Camera) {
+        self.uniform = SunUniform::new(sun, camera);
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+        self.passes.write(queue, &self.uniform.cascades);
+    }
+
+    // The layer of the shadow map that each cascade is drawn into, along with the bind group that draws it
+    pub(crate) fn cascades(&self) -> impl Iterator<Item = (&wgpu::TextureView, &wgpu::BindGroup)> {
+        self.passes.layers()
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct PointShadowUniform {
+    // Takes a world position to each face of each light's cube map, six faces to a light
+    pub(crate) faces: [[[f32; 4]; 4]; MAX_POINT_SHADOWS * 6],
+    // Index of the light that each cube map belongs to, or -1 when the cube map is unused
+    pub(crate) lights: [i32; MAX_POINT_SHADOWS]
+}
+
+impl PointShadowUniform {
+    // Gives cube maps to the lights that matter most to the camera: bright ones that are close by
+    pub(crate) fn new(lights: &[Light], camera: &Camera, count: usize) -> Self {
+        let score = |light: &Light| {
+            let position = Point3::new(light.position[0], light.position[1], light.position[2]);
+            light.color[3] / (1.0 + camera.eye.distance2(position))
+        };
+
+        let mut candidates = lights
+            .iter()
+            .enumerate()
+            .filter(|(_, light)| light.radius() > POINT_SHADOW_NEAR)
+            .map(|(index, light)| (index, score(light)))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let mut uniform = Self {
+            faces: [Matrix4::identity().into(); MAX_POINT_SHADOWS * 6],
+            lights: [-1; MAX_POINT_SHADOWS]
+        };
+
+        for (slot, (index, _)) in candidates.into_iter().take(count.min(MAX_POINT_SHADOWS)).enumerate() {
+            let light = &lights[index];
+            let position = Point3::new(light.position[0], light.position[1], light.position[2]);
+            let projection = cgmath::perspective(cgmath::Deg(90.0), 1.0, POINT_SHADOW_NEAR, light.radius());
+
+            for (face, (direction, up)) in CUBE_FACES.iter().enumerate() {
+                let view = Matrix4::look_to_rh(position, *direction, *up);
+                uniform.faces[slot * 6 + face] = (Camera::MATRIX_CORRECTION_FOR_WGPU * projection * view).into();
+            }
+
+            uniform.lights[slot] = index as i32;
+        }
+
+        uniform
+    }
+
+    // Number of cube maps that have to be drawn
+    pub(crate) fn count(&self) -> usize {
+        self.lights.iter().filter(|light| **light >= 0).count()
+    }
+}
+
+// Cube-map shadows for a few of the point lights, which are drawn before the rest of the scene
+// Each cube map is six layers of a 2D array, so that the faces can be drawn like any other shadow map
+pub(crate) struct PointShadows {
+    pub(crate) uniform: PointShadowUniform,
+    uniform_buffer: wgpu::Buffer,
+    passes: ShadowPasses,
+    pub(crate) pipeline: wgpu::RenderPipeline
+}
+
+impl PointShadows {
+    pub(crate) fn new(device: &wgpu::Device, camera: &Camera) -> Self {
+        let uniform = PointShadowUniform::new(&[], camera, 0);
+
+        let uniform_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(&[uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
+            }
+        );
+
+        // The GL backend takes square textures with a multiple of six layers to be cube maps,
+        // which can't be read as a 2D array, so a spare layer is added
+        let passes = ShadowPasses::new(device, POINT_SHADOW_MAP_SIZE, MAX_POINT_SHADOWS * 6 + 1);
+        let pipeline = create_depth_pipeline(
+            device,
+            &passes.bind_group_layout,
+            &device.create_shader_module(wgpu::include_wgsl!("shadow.wgsl"))
+        );
+
+        Self {
+            uniform,
+            uniform_buffer,
+            passes,
+            pipeline
+        }
+    }
+
+    pub(crate) fn update(&mut self, queue: &wgpu::Queue, lights: &[Light], camera: &Camera, count: usize) {
+        self.uniform = PointShadowUniform::new(lights, camera, count);
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+        self.passes.write(queue, &self.uniform.faces[..self.uniform.count() * 6]);
+    }
+
+    // The layer of the shadow map that each face in use is drawn into, along with the bind group that draws it
+    pub(crate) fn faces(&self) -> impl Iterator<Item = (&wgpu::TextureView, &wgpu::BindGroup)> {
+        self.passes.layers().take(self.uniform.count() * 6)
+    }
+}
+
+// A depth texture with a layer for each shadow map, and a matrix for each layer to draw it with
+struct ShadowPasses {
+    view: wgpu::TextureView,
+    layer_views: Vec<wgpu::TextureView>,
+    layer_buffers: Vec<wgpu::Buffer>,
+    layer_bind_groups: Vec<wgpu::BindGroup>,
+    // The depth pipeline only needs the matrix of the layer that it is drawing
+    bind_group_layout: wgpu::BindGroupLayout
+}
+
+impl ShadowPasses {
+    fn new(device: &wgpu::Device, size: u32, layers: usize) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: layers as u32
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+        } );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        } );
+
+        // Each shadow map is drawn into its own layer
+        let layer_views = (0..layers as u32)
+            .map(|layer| texture.create_view(&wgpu::TextureViewDescriptor {
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: layer,
+                array_layer_count: NonZeroU32::new(1),
+                ..Default::default()
+            } ))
+            .collect::<Vec<_>>();
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }
+            ],
+            label: None
+        } );
+
+        let identity: [[f32; 4]; 4] = Matrix4::identity().into();
+        let layer_buffers = (0..layers)
+            .map(|_| device.create_buffer_init(
+                &wgpu::util::BufferInitDescriptor {
+                    label: None,
+                    contents: bytemuck::cast_slice(&[identity]),
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
+                }
+            ))
+            .collect::<Vec<_>>();
+
+        let layer_bind_groups = layer_buffers
+            .iter()
+            .map(|buffer| device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding(),
+                    }
+                ],
+                label: None
+            } ))
+            .collect::<Vec<_>>();
+
+        Self {
+            view,
+            layer_views,
+            layer_buffers,
+            layer_bind_groups,
+            bind_group_layout
+        }
+    }
+
+    // Writes the matrices of the first layers
+    fn write(&self, queue: &wgpu::Queue, matrices: &[[[f32; 4]; 4]]) {
+        for (buffer, matrix) in self.layer_buffers.iter().zip(matrices.iter()) {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[*matrix]));
+        }
+    }
+
+    fn layers(&self) -> impl Iterator<Item = (&wgpu::TextureView, &wgpu::BindGroup)> {
+        self.layer_views.iter().zip(self.layer_bind_groups.iter())
+    }
+}
+
+// Lets the main pipeline read the sun's and the point lights' shadow maps, along with the uniforms that describe them
+pub(crate) fn create_shadow_bind_group(
+    device: &wgpu::Device,
+    sun_shadows: &SunShadows,
+    point_shadows: &PointShadows
+) -> (wgpu::BindGroupLayout, wgpu::BindGroup) {
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2Array,
+                    multisampled: false
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2Array,
+                    multisampled: false
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                count: None,
+            }
+        ],
+        label: None
+    } );
+
+    // Compares against the depth in the shadow map, with bilinear filtering between texels
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: None,
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        compare: Some(wgpu::CompareFunction::LessEqual),
+        ..Default::default()
+    } );
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: sun_shadows.uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&sun_shadows.passes.view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: point_shadows.uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(&point_shadows.passes.view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            }
+        ],
+        label: None
+    } );
+
+    (layout, bind_group)
+}
+
+// Draws the depth of the scene's geometry, without any color
+pub(crate) fn create_depth_pipeline(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    shader: &wgpu::ShaderModule
+) -> wgpu::RenderPipeline {
+    let layout = device.create_pipeline_layout(
+        &wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[
+                bind_group_layout
+            ],
+            push_constant_ranges: &[]
+        }
+    );
+
+    device.create_render_pipeline(
+        &wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    Vertex::description(),
+                    Instance::description()
+                ]
+            },
+            fragment: None,
+            // Both faces are drawn, so that open meshes still cast shadows
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false
+            },
+            // Biased to stop surfaces from shadowing themselves
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0
+                }
+            } ),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None
+        }
+    )
+}
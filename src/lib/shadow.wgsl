@@ -0,0 +1,25 @@
+struct CascadeUniform {
+    projection: mat4x4<f32>
+};
+
+@group(0) @binding(0)
+var<uniform> cascade: CascadeUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>
+};
+
+struct InstanceInput {
+    @location(6) model_0: vec4<f32>,
+    @location(7) model_1: vec4<f32>,
+    @location(8) model_2: vec4<f32>,
+    @location(9) model_3: vec4<f32>
+};
+
+// Only depth is written, so there is no fragment shader
+@vertex
+fn vs_main(model: VertexInput, instance: InstanceInput) -> @builtin(position) vec4<f32> {
+    let model_matrix = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+
+    return cascade.projection * model_matrix * vec4<f32>(model.position, 1.0);
+}
@@ -3,10 +3,14 @@ use winit::window;
 use wgpu::util::DeviceExt;
 
 use crate::{
+    buffer::DynamicBuffer,
     camera,
     Vertex,
-    world,
+    vertex::{ ParticleVertex, Instance },
+    world::{ self, MeshId, Material, mesh::MeshRange, material::MaterialUniform, transparency::TransparentDraw },
     light,
+    shadow::{ self, SunShadows, PointShadows },
+    texture::AtlasTextures,
 };
 
 pub(crate) struct State {
@@ -17,18 +21,41 @@ pub(crate) struct State {
     pub(crate) device: wgpu::Device,
     pub(crate) queue: wgpu::Queue,
     pub(crate) surface_config: wgpu::SurfaceConfiguration,
-    pub(crate) vertex_buffer: wgpu::Buffer,
-    pub(crate) index_buffer: wgpu::Buffer,
-    pub(crate) index_count: u32,
+    pub(crate) tile_vertex_buffer: DynamicBuffer,
+    pub(crate) tile_index_buffer: DynamicBuffer,
+    pub(crate) tile_index_count: u32,
+    pub(crate) transparent_index_buffer: DynamicBuffer,
+    pub(crate) transparent_draws: Vec<TransparentDraw>,
+    pub(crate) debug_vertex_buffer: DynamicBuffer,
+    pub(crate) debug_index_buffer: DynamicBuffer,
+    pub(crate) debug_index_count: u32,
+    pub(crate) mesh_vertex_buffer: DynamicBuffer,
+    pub(crate) mesh_index_buffer: DynamicBuffer,
+    pub(crate) mesh_ranges: Vec<MeshRange>,
+    pub(crate) instance_buffer: DynamicBuffer,
+    pub(crate) instance_batches: Vec<(MeshId, std::ops::Range<u32>)>,
     pub(crate) camera: camera::Camera,
     pub(crate) camera_uniform: camera::CameraUniform,
     pub(crate) camera_buffer: wgpu::Buffer,
     pub(crate) camera_bind_group: wgpu::BindGroup,
-    pub(crate) light_sources: light::LightSources,
-    pub(crate) light_buffer: wgpu::Buffer,
+    pub(crate) light_buffer: DynamicBuffer,
+    pub(crate) cluster_grid_buffer: wgpu::Buffer,
+    pub(crate) cluster_buffer: DynamicBuffer,
+    pub(crate) light_index_buffer: DynamicBuffer,
+    pub(crate) material_buffer: DynamicBuffer,
+    pub(crate) light_bind_group_layout: wgpu::BindGroupLayout,
     pub(crate) light_bind_group: wgpu::BindGroup,
+    pub(crate) sun_shadows: SunShadows,
+    pub(crate) point_shadows: PointShadows,
+    pub(crate) shadow_bind_group: wgpu::BindGroup,
+    pub(crate) atlas_textures: AtlasTextures,
+    pub(crate) particle_vertex_buffer: DynamicBuffer,
+    pub(crate) particle_index_buffer: DynamicBuffer,
+    pub(crate) particle_index_count: u32,
     pub(crate) depth_texture_view: wgpu::TextureView,
-    pub(crate) render_pipeline: wgpu::RenderPipeline
+    pub(crate) render_pipeline: wgpu::RenderPipeline,
+    pub(crate) transparent_pipeline: wgpu::RenderPipeline,
+    pub(crate) particle_pipeline: wgpu::RenderPipeline
 }
 
 impl State {
@@ -76,23 +103,30 @@ impl State {
 
         surface.configure(&device, &surface_config);
 
-        let vertex_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: &[],
-                usage: wgpu::BufferUsages::VERTEX
-            }
-        );
+        // Geometry buffers are kept for the lifetime of the state, and grow as needed
+        let tile_vertex_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::VERTEX);
+        let tile_index_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::INDEX);
+        let tile_index_count = 0u32;
 
-        let index_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: &[],
-                usage: wgpu::BufferUsages::INDEX,
-            }
-        );
+        // Indices of the tile faces that can be seen through, which are sorted again on every tick
+        let transparent_index_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::INDEX);
+        let transparent_draws = Vec::new();
+
+        let debug_vertex_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::VERTEX);
+        let debug_index_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::INDEX);
+        let debug_index_count = 0u32;
 
-        let index_count = 0u32;
+        let mesh_vertex_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::VERTEX);
+        let mesh_index_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::INDEX);
+        let mesh_ranges = Vec::new();
+
+        let mut instance_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::VERTEX);
+        instance_buffer.write(&device, &queue, &[Instance::default()]);
+        let instance_batches = Vec::new();
+
+        let particle_vertex_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::VERTEX);
+        let particle_index_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::INDEX);
+        let particle_index_count = 0u32;
 
         let camera = camera::Camera::default();
 
@@ -141,19 +175,21 @@ impl State {
             }
         ) };
 
-        let light_sources = light::LightSources {
-            light_uniforms: [
-                light::Light::default(); 
-                light::MAX_LIGHT_SOURCES
-            ]
-        };
+        // Lights are split into clusters, which are rebuilt on every tick
+        let light_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::STORAGE);
+        let cluster_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::STORAGE);
+        let light_index_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::STORAGE);
 
-        let light_buffer = device.create_buffer_init(
+        // Materials are looked up by the index in each vertex or instance, and the default comes first
+        let mut material_buffer = DynamicBuffer::new(&device, wgpu::BufferUsages::STORAGE);
+        material_buffer.write(&device, &queue, &[MaterialUniform::new(&Material::default())]);
+
+        let cluster_grid_buffer = device.create_buffer_init(
             &wgpu::util::BufferInitDescriptor {
                 label: None,
-                contents: bytemuck::cast_slice(&[light_sources]),
+                contents: bytemuck::cast_slice(&[light::LightClusters::build(&[]).grid]),
                 usage: { 
-                    wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST
+                    wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
                 },
             }
         );
@@ -163,8 +199,47 @@ impl State {
                 entries: &[
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
-                        visibility: wgpu::ShaderStages::VERTEX 
-                            | wgpu::ShaderStages::FRAGMENT,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
                         ty: wgpu::BindingType::Buffer {
                             ty: wgpu::BufferBindingType::Storage { read_only: true },
                             has_dynamic_offset: false,
@@ -177,18 +252,25 @@ impl State {
             }
         ) };
 
-        let light_bind_group = { 
-            device.create_bind_group(&wgpu::BindGroupDescriptor {
-                layout: &light_bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: light_buffer.as_entire_binding(),
-                    }
-                ],
-                label: None
-            }
-        ) };
+        let light_bind_group = create_light_bind_group(
+            &device, 
+            &light_bind_group_layout, 
+            &light_buffer, 
+            &cluster_grid_buffer, 
+            &cluster_buffer, 
+            &light_index_buffer,
+            &material_buffer
+        );
+
+        let sun_shadows = SunShadows::new(&device, &camera);
+        let point_shadows = PointShadows::new(&device, &camera);
+        let (shadow_bind_group_layout, shadow_bind_group) = shadow::create_shadow_bind_group(
+            &device,
+            &sun_shadows,
+            &point_shadows
+        );
+
+        let atlas_textures = AtlasTextures::new(&device, &queue);
 
         let shader = device.create_shader_module(
             wgpu::include_wgsl!("shader.wgsl")
@@ -201,26 +283,60 @@ impl State {
                 label: None,
                 bind_group_layouts: &[
                     &camera_bind_group_layout,
-                    &light_bind_group_layout
+                    &light_bind_group_layout,
+                    &shadow_bind_group_layout,
+                    &atlas_textures.bind_group_layout
+                ],
+                push_constant_ranges: &[]
+            }
+        );
+
+        let render_pipeline = create_scene_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &shader,
+            surface_config.format,
+            false
+        );
+
+        // Drawn after everything else, blending over what is behind without hiding what is further back
+        let transparent_pipeline = create_scene_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &shader,
+            surface_config.format,
+            true
+        );
+
+        let particle_shader = device.create_shader_module(
+            wgpu::include_wgsl!("particle.wgsl")
+        );
+
+        let particle_pipeline_layout = device.create_pipeline_layout(
+            &wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[
+                    &camera_bind_group_layout
                 ],
                 push_constant_ranges: &[]
             }
         );
 
-        let render_pipeline = device.create_render_pipeline(
+        // Particles are unlit and have no back faces
+        let particle_pipeline = device.create_render_pipeline(
             &wgpu::RenderPipelineDescriptor {
                 label: None,
-                layout: Some(&render_pipeline_layout),
+                layout: Some(&particle_pipeline_layout),
                 vertex: wgpu::VertexState {
-                    module: &shader,
+                    module: &particle_shader,
                     entry_point: "vs_main",
                     buffers: &[
-                        Vertex::description()
+                        ParticleVertex::description()
                     ]
                 },
                 fragment: Some(
                     wgpu::FragmentState {
-                        module: &shader,
+                        module: &particle_shader,
                         entry_point: "fs_main",
                         targets: &[
                             Some(wgpu::ColorTargetState {
@@ -235,7 +351,7 @@ impl State {
                     topology: wgpu::PrimitiveTopology::TriangleList,
                     strip_index_format: None,
                     front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
+                    cull_mode: None,
                     polygon_mode: wgpu::PolygonMode::Fill,
                     unclipped_depth: false,
                     conservative: false
@@ -263,18 +379,41 @@ impl State {
             device,
             queue,
             surface_config,
-            vertex_buffer,
-            index_buffer,
-            index_count,
+            tile_vertex_buffer,
+            tile_index_buffer,
+            tile_index_count,
+            transparent_index_buffer,
+            transparent_draws,
+            debug_vertex_buffer,
+            debug_index_buffer,
+            debug_index_count,
+            mesh_vertex_buffer,
+            mesh_index_buffer,
+            mesh_ranges,
+            instance_buffer,
+            instance_batches,
             camera,
             camera_uniform,
             camera_buffer,
             camera_bind_group,
-            light_sources,
             light_buffer,
+            cluster_grid_buffer,
+            cluster_buffer,
+            light_index_buffer,
+            material_buffer,
+            light_bind_group_layout,
             light_bind_group,
+            sun_shadows,
+            point_shadows,
+            shadow_bind_group,
+            atlas_textures,
+            particle_vertex_buffer,
+            particle_index_buffer,
+            particle_index_count,
             depth_texture_view,
-            render_pipeline
+            render_pipeline,
+            transparent_pipeline,
+            particle_pipeline
         }
     }
 
@@ -296,16 +435,90 @@ impl State {
     pub(crate) fn update(&mut self) {
         self.world.resolve_entity_lifetimes();
         self.world.resolve_entity_physics();
+        self.world.resolve_triggers();
+        self.world.resolve_particles();
+        self.world.resolve_voxel_light();
+        self.world.resolve_ambient_occlusion();
+
+        if let Some(atlas) = self.world.take_texture_atlas_change() {
+            self.atlas_textures.update(&self.device, &self.queue, atlas);
+        }
         
-       (self.vertex_buffer, self.index_buffer, self.index_count) = self.world.build_geometry_buffers(&mut self.device);
-       (self.light_sources, ..) = self.world.build_light_sources();
+        // Static tile geometry is only written when it changes
+        if let Some((first_vertex, first_index)) = self.world.take_tile_geometry_changes() {
+            let (vertices, indices) = self.world.tile_geometry();
+
+            self.tile_vertex_buffer.update(&self.device, &self.queue, vertices, first_vertex);
+            self.tile_index_buffer.update(&self.device, &self.queue, indices, first_index);
+            self.tile_index_count = indices.len() as u32;
+        }
+
+        // Meshes are never removed, so only the ones that were added need to be written
+        if self.world.mesh_count() != self.mesh_ranges.len() {
+            let (vertices, indices, ranges) = self.world.mesh_geometry();
+            let first = &ranges[self.mesh_ranges.len()];
+
+            self.mesh_vertex_buffer.update(&self.device, &self.queue, vertices, first.base_vertex as usize);
+            self.mesh_index_buffer.update(&self.device, &self.queue, indices, first.indices.start as usize);
+            self.mesh_ranges = ranges.to_vec();
+        }
+
+        let batches = self.world.build_instances(self.camera.eye);
+        self.instance_buffer.write(&self.device, &self.queue, &batches.instances);
+        self.instance_batches = batches.opaque;
+
+        // Transparent faces are sorted by their distance to the camera, which changes on every tick
+        let (transparent_indices, transparent_draws) = self.world.build_transparent_draws(self.camera.eye, &batches.transparent);
+        self.transparent_index_buffer.write(&self.device, &self.queue, &transparent_indices);
+        self.transparent_draws = transparent_draws;
+
+        let debug_geometry = self.world.build_debug_geometry();
+        self.debug_vertex_buffer.write(&self.device, &self.queue, &debug_geometry.vertices);
+        self.debug_index_buffer.write(&self.device, &self.queue, &debug_geometry.indices);
+        self.debug_index_count = debug_geometry.indices.len() as u32;
+
+        let (particle_vertices, particle_indices) = self.world.build_particle_geometry();
+        self.particle_vertex_buffer.write(&self.device, &self.queue, &particle_vertices);
+        self.particle_index_buffer.write(&self.device, &self.queue, &particle_indices);
+        self.particle_index_count = particle_indices.len() as u32;
+
+        let light_sources = self.world.build_light_sources();
+        let clusters = light::LightClusters::build(&light_sources);
+
+        // Entity materials are gathered every tick, but are only written when they differ from the last ones
+        let materials_grown = self.world
+            .take_material_changes()
+            .is_some_and(|materials| self.material_buffer.write(&self.device, &self.queue, &materials));
+
+        let grown = [
+            self.light_buffer.write(&self.device, &self.queue, &light_sources),
+            self.cluster_buffer.write(&self.device, &self.queue, &clusters.clusters),
+            self.light_index_buffer.write(&self.device, &self.queue, &clusters.indices),
+            materials_grown
+        ];
 
         self.queue.write_buffer(
-            &self.light_buffer, 
+            &self.cluster_grid_buffer, 
             0, 
-            bytemuck::cast_slice(&[self.light_sources])
+            bytemuck::cast_slice(&[clusters.grid])
         );
 
+        // Buffers that grew were replaced, so the bind group has to refer to the new ones
+        if grown.contains(&true) {
+            self.light_bind_group = create_light_bind_group(
+                &self.device, 
+                &self.light_bind_group_layout, 
+                &self.light_buffer, 
+                &self.cluster_grid_buffer, 
+                &self.cluster_buffer, 
+                &self.light_index_buffer,
+                &self.material_buffer
+            );
+        }
+
+        self.sun_shadows.update(&self.queue, self.world.sun(), &self.camera);
+        self.point_shadows.update(&self.queue, &light_sources, &self.camera, self.world.point_shadow_count());
+
         self.camera_uniform.update_projection(&self.camera);
         self.queue.write_buffer(
             &self.camera_buffer, 
@@ -314,6 +527,39 @@ impl State {
         );
     }
 
+    // Draws the tiles and entity meshes, for every pass that needs the scene's geometry
+    // The pipeline and its bind groups must already be set
+    fn draw_geometry<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        // World-space geometry is drawn with the first, untransformed instance
+        render_pass.set_vertex_buffer(
+            1, 
+            self.instance_buffer.buffer().slice(..));
+
+        // Tiles
+        render_pass.set_vertex_buffer(
+            0, 
+            self.tile_vertex_buffer.buffer().slice(..));
+        render_pass.set_index_buffer(
+            self.tile_index_buffer.buffer().slice(..), 
+            wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.tile_index_count, 0, 0..1);
+
+        // Entity meshes, one draw call for each mesh
+        if !self.instance_batches.is_empty() {
+            render_pass.set_vertex_buffer(
+                0, 
+                self.mesh_vertex_buffer.buffer().slice(..));
+            render_pass.set_index_buffer(
+                self.mesh_index_buffer.buffer().slice(..), 
+                wgpu::IndexFormat::Uint32);
+
+            for (mesh, instances) in self.instance_batches.iter() {
+                let range = &self.mesh_ranges[mesh.0];
+                render_pass.draw_indexed(range.indices.clone(), range.base_vertex, instances.clone());
+            }
+        }
+    }
+
     pub(crate) fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         let output = self.surface.get_current_texture()?;
         let view = output.texture.create_view(
@@ -326,6 +572,34 @@ impl State {
             }
         );
 
+        // Shadows are drawn before the scene that they fall on
+        let sun_passes = self.sun_shadows.cascades()
+            .filter(|_| self.sun_shadows.uniform.is_enabled())
+            .map(|pass| (pass, &self.sun_shadows.pipeline));
+        let point_passes = self.point_shadows.faces()
+            .map(|pass| (pass, &self.point_shadows.pipeline));
+
+        for ((shadow_view, shadow_bind_group), shadow_pipeline) in sun_passes.chain(point_passes) {
+            let mut shadow_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: None,
+                    color_attachments: &[],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: shadow_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: true,
+                        } ),
+                        stencil_ops: None,
+                    } )
+                }
+            );
+
+            shadow_pass.set_pipeline(shadow_pipeline);
+            shadow_pass.set_bind_group(0, shadow_bind_group, &[]);
+            self.draw_geometry(&mut shadow_pass);
+        }
+
         {
             let mut render_pass = encoder.begin_render_pass(
                 &wgpu::RenderPassDescriptor {
@@ -358,18 +632,79 @@ impl State {
             // Set render pipeline
             render_pass.set_pipeline(&self.render_pipeline);
 
-            // Camera and light bind groups
+            // Camera, light, shadow and texture bind groups
             render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
             render_pass.set_bind_group(1, &self.light_bind_group, &[]);
+            render_pass.set_bind_group(2, &self.shadow_bind_group, &[]);
+            render_pass.set_bind_group(3, &self.atlas_textures.bind_group, &[]);
+
+            self.draw_geometry(&mut render_pass);
+
+            // Collider outlines
+            if self.debug_index_count > 0 {
+                render_pass.set_vertex_buffer(
+                    0, 
+                    self.debug_vertex_buffer.buffer().slice(..));
+                render_pass.set_index_buffer(
+                    self.debug_index_buffer.buffer().slice(..), 
+                    wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..self.debug_index_count, 0, 0..1);
+            }
 
-            // Set vertex and index buffers
+            // Particles
+            render_pass.set_pipeline(&self.particle_pipeline);
             render_pass.set_vertex_buffer(
                 0, 
-                self.vertex_buffer.slice(..));
+                self.particle_vertex_buffer.buffer().slice(..));
             render_pass.set_index_buffer(
-                self.index_buffer.slice(..), 
+                self.particle_index_buffer.buffer().slice(..), 
                 wgpu::IndexFormat::Uint32);
-            render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+            render_pass.draw_indexed(0..self.particle_index_count, 0, 0..1);
+
+            // Transparent tiles and meshes, from back to front over everything else
+            render_pass.set_pipeline(&self.transparent_pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.light_bind_group, &[]);
+            render_pass.set_bind_group(2, &self.shadow_bind_group, &[]);
+            render_pass.set_bind_group(3, &self.atlas_textures.bind_group, &[]);
+            render_pass.set_vertex_buffer(
+                1, 
+                self.instance_buffer.buffer().slice(..));
+
+            // Tile triangles and meshes are drawn in the order they were sorted in, and the buffers are only
+            // switched when going from one to the other
+            let mut drawing_tiles = None;
+            for draw in self.transparent_draws.iter() {
+                match draw {
+                    TransparentDraw::Tiles(indices) => {
+                        if drawing_tiles != Some(true) {
+                            render_pass.set_vertex_buffer(
+                                0, 
+                                self.tile_vertex_buffer.buffer().slice(..));
+                            render_pass.set_index_buffer(
+                                self.transparent_index_buffer.buffer().slice(..), 
+                                wgpu::IndexFormat::Uint32);
+                            drawing_tiles = Some(true);
+                        }
+
+                        render_pass.draw_indexed(indices.clone(), 0, 0..1);
+                    },
+                    TransparentDraw::Mesh(mesh, instance) => {
+                        if drawing_tiles != Some(false) {
+                            render_pass.set_vertex_buffer(
+                                0, 
+                                self.mesh_vertex_buffer.buffer().slice(..));
+                            render_pass.set_index_buffer(
+                                self.mesh_index_buffer.buffer().slice(..), 
+                                wgpu::IndexFormat::Uint32);
+                            drawing_tiles = Some(false);
+                        }
+
+                        let range = &self.mesh_ranges[mesh.0];
+                        render_pass.draw_indexed(range.indices.clone(), range.base_vertex, *instance..*instance + 1);
+                    }
+                }
+            }
         }
     
         self.queue.submit(
@@ -405,4 +740,103 @@ pub(crate) fn create_depth_texture(
 
     let texture = device.create_texture(&desc);
     texture.create_view(&wgpu::TextureViewDescriptor::default())
-}
\ No newline at end of file
+}
+
+pub(crate) fn create_light_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    light_buffer: &DynamicBuffer,
+    cluster_grid_buffer: &wgpu::Buffer,
+    cluster_buffer: &DynamicBuffer,
+    light_index_buffer: &DynamicBuffer,
+    material_buffer: &DynamicBuffer
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: light_buffer.buffer().as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: cluster_grid_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: cluster_buffer.buffer().as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: light_index_buffer.buffer().as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: material_buffer.buffer().as_entire_binding(),
+            }
+        ],
+        label: None
+    } )
+}
+
+// Draws tiles and meshes with lighting, shadows and textures
+// Transparent pipelines blend with what is already drawn, and don't write depth
+pub(crate) fn create_scene_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    transparent: bool
+) -> wgpu::RenderPipeline {
+    let blend = if transparent { wgpu::BlendState::ALPHA_BLENDING } else { wgpu::BlendState::REPLACE };
+
+    device.create_render_pipeline(
+        &wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    Vertex::description(),
+                    Instance::description()
+                ]
+            },
+            fragment: Some(
+                wgpu::FragmentState {
+                    module: shader,
+                    entry_point: "fs_main",
+                    targets: &[
+                        Some(wgpu::ColorTargetState {
+                            format,
+                            blend: Some(blend),
+                            write_mask: wgpu::ColorWrites::ALL
+                        } )
+                    ],
+                }
+            ),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: !transparent,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default()
+            } ),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None
+        }
+    )
+}
@@ -0,0 +1,290 @@
+use cgmath::{
+    Point3,
+    Vector3,
+    InnerSpace,
+    EuclideanSpace,
+    Zero
+};
+
+use crate::world::{
+    World,
+    Entity,
+    EntityHandle
+};
+
+// Tunable settings for an entity's steering, all in tiles and ticks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SteeringConfig {
+    // Speed that the behaviours steer the entity towards
+    pub max_speed: f32,
+    // Largest change in velocity that steering can make in a single tick
+    pub max_force: f32,
+    // Distance from the target at which arriving entities begin to slow down
+    pub slowing_radius: f32,
+    // Wandering entities steer towards a point on a circle of this radius...
+    pub wander_radius: f32,
+    // ...which is projected this far in front of them
+    pub wander_distance: f32,
+    // Largest change in the angle of the wander target on each tick, in radians
+    pub wander_jitter: f32,
+    // How far ahead to look for tiles to avoid
+    pub avoidance_distance: f32,
+    // Entities closer than this are steered away from
+    pub separation_radius: f32,
+    // Entities closer than this are part of the same flock
+    pub neighbour_radius: f32,
+    // Steering ignores the vertical axis, leaving it to gravity and jumps
+    pub planar: bool
+}
+
+impl Default for SteeringConfig {
+    fn default() -> Self {
+        Self {
+            max_speed: 0.15,
+            max_force: 0.02,
+            slowing_radius: 2.0,
+            wander_radius: 1.0,
+            wander_distance: 2.0,
+            wander_jitter: 0.3,
+            avoidance_distance: 2.0,
+            separation_radius: 1.0,
+            neighbour_radius: 3.0,
+            planar: true
+        }
+    }
+}
+
+// Relative strength of each part of flocking
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FlockWeights {
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32
+}
+
+impl Default for FlockWeights {
+    fn default() -> Self {
+        Self {
+            separation: 1.5,
+            alignment: 1.0,
+            cohesion: 1.0
+        }
+    }
+}
+
+// Produces changes in velocity for an entity
+// Behaviours return a steering vector, which can be added to others and applied once per tick
+#[derive(Clone, Debug)]
+pub struct Steering {
+    pub config: SteeringConfig,
+    wander_angle: f32,
+    seed: u32
+}
+
+impl Steering {
+    // The seed keeps wandering entities from all turning the same way
+    pub fn new(config: SteeringConfig, seed: u32) -> Self {
+        Self {
+            config,
+            wander_angle: 0.0,
+            seed: seed.max(1)
+        }
+    }
+
+    // Removes the vertical part of a vector when steering is planar
+    fn flatten(&self, vector: Vector3<f32>) -> Vector3<f32> {
+        if self.config.planar {
+            Vector3::new(vector.x, 0.0, vector.z)
+        } else {
+            vector
+        }
+    }
+
+    // Steering that changes the entity's velocity to the desired velocity
+    fn steer_towards(&self, entity: &dyn Entity, desired: Vector3<f32>) -> Vector3<f32> {
+        self.flatten(desired - entity.velocity())
+    }
+
+    fn towards(&self, from: Point3<f32>, to: Point3<f32>, speed: f32) -> Vector3<f32> {
+        let offset = self.flatten(to - from);
+        if offset.magnitude2() == 0.0 {
+            Vector3::zero()
+        } else {
+            offset.normalize_to(speed)
+        }
+    }
+
+    pub fn seek(&self, entity: &dyn Entity, target: Point3<f32>) -> Vector3<f32> {
+        let desired = self.towards(entity.center(), target, self.config.max_speed);
+        self.steer_towards(entity, desired)
+    }
+
+    pub fn flee(&self, entity: &dyn Entity, threat: Point3<f32>) -> Vector3<f32> {
+        let desired = -self.towards(entity.center(), threat, self.config.max_speed);
+        self.steer_towards(entity, desired)
+    }
+
+    // Seeks the target, slowing down to stop on it
+    pub fn arrive(&self, entity: &dyn Entity, target: Point3<f32>) -> Vector3<f32> {
+        let distance = self.flatten(target - entity.center()).magnitude();
+        let speed = self.config.max_speed * (distance / self.config.slowing_radius).min(1.0);
+
+        let desired = self.towards(entity.center(), target, speed);
+        self.steer_towards(entity, desired)
+    }
+
+    // How far ahead to predict a moving target, based on how long it would take to reach it
+    fn prediction_time(&self, entity: &dyn Entity, target: &dyn Entity) -> f32 {
+        let distance = (target.center() - entity.center()).magnitude();
+        distance / self.config.max_speed.max(f32::EPSILON)
+    }
+
+    // Seeks the point that a moving target is heading towards
+    pub fn pursue(&self, entity: &dyn Entity, target: &dyn Entity) -> Vector3<f32> {
+        let predicted = target.center() + target.velocity() * self.prediction_time(entity, target);
+        self.seek(entity, predicted)
+    }
+
+    // Flees the point that a moving threat is heading towards
+    pub fn evade(&self, entity: &dyn Entity, threat: &dyn Entity) -> Vector3<f32> {
+        let predicted = threat.center() + threat.velocity() * self.prediction_time(entity, threat);
+        self.flee(entity, predicted)
+    }
+
+    // Meanders by steering towards a target that drifts around a circle in front of the entity
+    pub fn wander(&mut self, entity: &dyn Entity) -> Vector3<f32> {
+        // Xorshift, so that wandering doesn't need a source of randomness
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+
+        let random = (self.seed as f32 / u32::MAX as f32) * 2.0 - 1.0;
+        self.wander_angle += random * self.config.wander_jitter;
+
+        let heading = match self.flatten(entity.velocity()) {
+            velocity if velocity.magnitude2() > 0.0 => velocity.normalize(),
+            _ => Vector3::unit_x()
+        };
+
+        let offset = Vector3::new(self.wander_angle.cos(), 0.0, self.wander_angle.sin()) * self.config.wander_radius;
+        let target = entity.center() + heading * self.config.wander_distance + offset;
+
+        self.seek(entity, target)
+    }
+
+    // Turns away from tiles that lie ahead of the entity
+    pub fn avoid_obstacles(&self, world: &World, entity: &dyn Entity) -> Vector3<f32> {
+        let velocity = self.flatten(entity.velocity());
+        if velocity.magnitude2() == 0.0 {
+            return Vector3::zero();
+        }
+
+        // Cast rays from the center and the sides of the entity, so that it doesn't clip corners
+        let heading = velocity.normalize();
+        let side = heading.cross(Vector3::unit_y()) * entity.collider().half_extents().x;
+
+        [Vector3::zero(), side, -side]
+            .iter()
+            .filter_map(|offset| world.raycast(entity.center() + offset, heading, self.config.avoidance_distance))
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+            .map(|hit| {
+                // Closer obstacles push harder
+                let urgency = 1.0 - hit.distance / self.config.avoidance_distance;
+                let normal = self.flatten(hit.normal);
+
+                let away = if normal.magnitude2() > 0.0 { normal.normalize() } else { -heading };
+                away * self.config.max_speed * urgency
+            } )
+            .unwrap_or_else(Vector3::zero)
+    }
+
+    fn neighbours(&self, world: &World, handle: &EntityHandle, radius: f32) -> Vec<EntityHandle> {
+        world.entities_in_radius(handle.borrow().center(), radius)
+            .into_iter()
+            .filter(|other| !other.ptr_eq(handle))
+            .collect()
+    }
+
+    // Steers away from nearby entities, more strongly the closer they are
+    pub fn separation(&self, world: &World, handle: &EntityHandle) -> Vector3<f32> {
+        let center = handle.borrow().center();
+
+        let push = self.neighbours(world, handle, self.config.separation_radius)
+            .iter()
+            .map(|other| self.flatten(center - other.borrow().center()))
+            .filter(|offset| offset.magnitude2() > 0.0)
+            .fold(Vector3::zero(), |push, offset| push + offset / offset.magnitude2());
+
+        if push.magnitude2() == 0.0 {
+            Vector3::zero()
+        } else {
+            self.steer_towards(&*handle.borrow(), push.normalize_to(self.config.max_speed))
+        }
+    }
+
+    // Matches the average velocity of nearby entities
+    pub fn alignment(&self, world: &World, handle: &EntityHandle) -> Vector3<f32> {
+        let neighbours = self.neighbours(world, handle, self.config.neighbour_radius);
+        if neighbours.is_empty() {
+            return Vector3::zero();
+        }
+
+        let average = neighbours
+            .iter()
+            .fold(Vector3::zero(), |sum, other| sum + other.borrow().velocity()) / neighbours.len() as f32;
+
+        let average = self.flatten(average);
+        if average.magnitude2() == 0.0 {
+            Vector3::zero()
+        } else {
+            self.steer_towards(&*handle.borrow(), average.normalize_to(self.config.max_speed))
+        }
+    }
+
+    // Seeks the center of nearby entities
+    pub fn cohesion(&self, world: &World, handle: &EntityHandle) -> Vector3<f32> {
+        let neighbours = self.neighbours(world, handle, self.config.neighbour_radius);
+        if neighbours.is_empty() {
+            return Vector3::zero();
+        }
+
+        let centers = neighbours
+            .iter()
+            .map(|other| other.borrow().center())
+            .collect::<Vec<_>>();
+
+        self.seek(&*handle.borrow(), Point3::centroid(&centers))
+    }
+
+    pub fn flock(&self, world: &World, handle: &EntityHandle, weights: FlockWeights) -> Vector3<f32> {
+        self.separation(world, handle) * weights.separation +
+            self.alignment(world, handle) * weights.alignment +
+            self.cohesion(world, handle) * weights.cohesion
+    }
+
+    // Changes the entity's velocity by the steering vector, limited to the maximum force
+    // Steering never pushes the entity past its maximum speed,
+    // but it doesn't slow down entities that were launched by something else
+    pub fn apply(&self, handle: &EntityHandle, steering: Vector3<f32>) {
+        let mut handle = handle.clone();
+        let mut entity = handle.borrow_mut();
+
+        let steering = self.flatten(steering);
+        let steering = if steering.magnitude() > self.config.max_force {
+            steering.normalize_to(self.config.max_force)
+        } else {
+            steering
+        };
+
+        let previous = self.flatten(entity.velocity());
+        let mut velocity = entity.velocity() + steering;
+
+        let steered = self.flatten(velocity);
+        let speed_limit = previous.magnitude().max(self.config.max_speed);
+        if steered.magnitude() > speed_limit {
+            velocity -= steered - steered.normalize_to(speed_limit);
+        }
+
+        entity.set_velocity(velocity);
+    }
+}
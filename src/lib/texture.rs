@@ -0,0 +1,383 @@
+use std::{
+    collections::HashMap,
+    fmt
+};
+
+// The atlas starts at this size, and doubles until every texture fits
+const MIN_ATLAS_SIZE: u32 = 64;
+const MAX_ATLAS_SIZE: u32 = 4096;
+
+// Texels copied from the edge of each texture around it, so that neighbours don't bleed in
+const GUTTER: u32 = 1;
+
+// Normal map texel that leaves the surface normal unchanged
+const FLAT_NORMAL: [u8; 4] = [128, 128, 255, 255];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureError {
+    // Name of the texture that couldn't be added or packed
+    pub name: String,
+    pub message: String
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in texture '{}'", self.message, self.name)
+    }
+}
+
+impl std::error::Error for TextureError {  }
+
+// Where a texture ended up in the atlas, in texture coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureRegion {
+    pub min: [f32; 2],
+    pub max: [f32; 2]
+}
+
+impl TextureRegion {
+    // Texture coordinates of a point on the texture, from (0, 0) at the top left to (1, 1) at the bottom right
+    pub fn uv(&self, u: f32, v: f32) -> [f32; 2] {
+        [
+            self.min[0] + (self.max[0] - self.min[0]) * u,
+            self.min[1] + (self.max[1] - self.min[1]) * v
+        ]
+    }
+}
+
+// Every texture in the world packed into one image, along with a matching image of normal maps
+pub struct TextureAtlas {
+    size: u32,
+    colors: Vec<u8>,
+    normals: Vec<u8>,
+    regions: HashMap<String, TextureRegion>
+}
+
+impl TextureAtlas {
+    pub fn region(&self, name: &str) -> Option<TextureRegion> {
+        self.regions.get(name).copied()
+    }
+
+    // Width and height in texels, since the atlas is always square
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub(crate) fn colors(&self) -> &[u8] {
+        &self.colors
+    }
+
+    pub(crate) fn normals(&self) -> &[u8] {
+        &self.normals
+    }
+}
+
+struct Image {
+    name: String,
+    width: u32,
+    height: u32,
+    colors: Vec<u8>,
+    // Textures without a normal map get a flat one
+    normals: Option<Vec<u8>>
+}
+
+// Collects PNG images, which are packed into an atlas once they have all been added
+#[derive(Default)]
+pub struct TextureAtlasBuilder {
+    images: Vec<Image>
+}
+
+impl TextureAtlasBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Replaces any texture that was already added with the same name
+    pub fn add_png(&mut self, name: &str, bytes: &[u8]) -> Result<&mut Self, TextureError> {
+        let (width, height, colors) = decode_png(name, bytes)?;
+        self.insert(Image { name: name.to_string(), width, height, colors, normals: None });
+
+        Ok(self)
+    }
+
+    // The normal map is in tangent space, with +x along the texture's u and +y against its v
+    pub fn add_png_with_normal_map(
+        &mut self,
+        name: &str,
+        bytes: &[u8],
+        normal_map: &[u8]
+    ) -> Result<&mut Self, TextureError> {
+        let (width, height, colors) = decode_png(name, bytes)?;
+        let (normal_width, normal_height, normals) = decode_png(name, normal_map)?;
+
+        if (normal_width, normal_height) != (width, height) {
+            return Err(TextureError {
+                name: name.to_string(),
+                message: format!(
+                    "normal map is {}x{} but the texture is {}x{}",
+                    normal_width, normal_height, width, height
+                )
+            } );
+        }
+
+        self.insert(Image { name: name.to_string(), width, height, colors, normals: Some(normals) });
+
+        Ok(self)
+    }
+
+    fn insert(&mut self, image: Image) {
+        self.images.retain(|other| other.name != image.name);
+        self.images.push(image);
+    }
+
+    // Packs the textures onto shelves, tallest first, in the smallest square that holds them all
+    pub fn build(&self) -> Result<TextureAtlas, TextureError> {
+        let mut order = (0..self.images.len()).collect::<Vec<_>>();
+        // Tallest first, then widest, so that each shelf wastes as little space as possible
+        order.sort_by_key(|i| std::cmp::Reverse((self.images[*i].height, self.images[*i].width)));
+
+        let mut size = MIN_ATLAS_SIZE;
+        let placements = loop {
+            if let Some(placements) = self.pack(&order, size) {
+                break placements;
+            }
+
+            if size >= MAX_ATLAS_SIZE {
+                let largest = &self.images[order[0]];
+                return Err(TextureError {
+                    name: largest.name.clone(),
+                    message: format!("textures don't fit in a {}x{} atlas", MAX_ATLAS_SIZE, MAX_ATLAS_SIZE)
+                } );
+            }
+
+            size *= 2;
+        };
+
+        let mut colors = [255u8; 4].repeat((size * size) as usize);
+        let mut normals = FLAT_NORMAL.repeat((size * size) as usize);
+        let mut regions = HashMap::new();
+
+        for (image, (x, y)) in placements {
+            let image = &self.images[image];
+            copy_with_gutter(&mut colors, size, &image.colors, image.width, image.height, x, y);
+            if let Some(image_normals) = &image.normals {
+                copy_with_gutter(&mut normals, size, image_normals, image.width, image.height, x, y);
+            }
+
+            let texel = (size as f32).recip();
+            regions.insert(image.name.clone(), TextureRegion {
+                min: [(x + GUTTER) as f32 * texel, (y + GUTTER) as f32 * texel],
+                max: [(x + GUTTER + image.width) as f32 * texel, (y + GUTTER + image.height) as f32 * texel]
+            } );
+        }
+
+        Ok(TextureAtlas { size, colors, normals, regions })
+    }
+
+    // Top left corner of each image and its gutter, or None if they don't all fit
+    fn pack(&self, order: &[usize], size: u32) -> Option<Vec<(usize, (u32, u32))>> {
+        let mut placements = Vec::with_capacity(order.len());
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+        for i in order {
+            let width = self.images[*i].width + GUTTER * 2;
+            let height = self.images[*i].height + GUTTER * 2;
+
+            // Start a new shelf when the current one is full
+            if x + width > size {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+
+            if x + width > size || y + height > size {
+                return None;
+            }
+
+            placements.push((*i, (x, y)));
+            x += width;
+            shelf_height = shelf_height.max(height);
+        }
+
+        Some(placements)
+    }
+}
+
+// Decodes any PNG into 8-bit RGBA
+fn decode_png(name: &str, bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), TextureError> {
+    let error = |message: String| TextureError { name: name.to_string(), message };
+
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info().map_err(|e| error(e.to_string()))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(|e| error(e.to_string()))?;
+    buffer.truncate(info.buffer_size());
+
+    let texels = (info.width * info.height) as usize;
+    let colors = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|texel| [texel[0], texel[1], texel[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|texel| [texel[0], texel[0], texel[0], texel[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer
+            .iter()
+            .flat_map(|value| [*value, *value, *value, 255])
+            .collect(),
+        png::ColorType::Indexed => return Err(error("indexed colors weren't expanded".to_string()))
+    };
+
+    if info.width == 0 || info.height == 0 || colors.len() != texels * 4 {
+        return Err(error("image has no texels".to_string()));
+    }
+
+    Ok((info.width, info.height, colors))
+}
+
+// Copies an image into the atlas with its gutter at (x, y), repeating the edge texels into the gutter
+fn copy_with_gutter(atlas: &mut [u8], size: u32, image: &[u8], width: u32, height: u32, x: u32, y: u32) {
+    for row in 0..height + GUTTER * 2 {
+        let source_row = row.saturating_sub(GUTTER).min(height - 1);
+
+        for column in 0..width + GUTTER * 2 {
+            let source_column = column.saturating_sub(GUTTER).min(width - 1);
+
+            let source = ((source_row * width + source_column) * 4) as usize;
+            let target = (((y + row) * size + x + column) * 4) as usize;
+            atlas[target..target + 4].copy_from_slice(&image[source..source + 4]);
+        }
+    }
+}
+
+// The atlas as the main pipeline reads it
+// Until the world has an atlas, every texture is plain white with a flat normal map
+pub(crate) struct AtlasTextures {
+    pub(crate) bind_group_layout: wgpu::BindGroupLayout,
+    pub(crate) bind_group: wgpu::BindGroup
+}
+
+impl AtlasTextures {
+    pub(crate) fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                }
+            ],
+            label: None
+        } );
+
+        let bind_group = create_atlas_bind_group(device, queue, &bind_group_layout, 1, &[255; 4], &FLAT_NORMAL);
+
+        Self { bind_group_layout, bind_group }
+    }
+
+    // Replaces the textures with the ones in the atlas
+    pub(crate) fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, atlas: &TextureAtlas) {
+        self.bind_group = create_atlas_bind_group(
+            device,
+            queue,
+            &self.bind_group_layout,
+            atlas.size(),
+            atlas.colors(),
+            atlas.normals()
+        );
+    }
+}
+
+fn create_atlas_bind_group(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
+    size: u32,
+    colors: &[u8],
+    normals: &[u8]
+) -> wgpu::BindGroup {
+    // Colors are stored in sRGB, while normals are plain directions
+    let extent = wgpu::Extent3d {
+        width: size,
+        height: size,
+        depth_or_array_layers: 1
+    };
+
+    let create_view = |texels: &[u8], format| {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST
+        } );
+
+        queue.write_texture(
+            texture.as_image_copy(),
+            texels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(size * 4),
+                rows_per_image: std::num::NonZeroU32::new(size)
+            },
+            extent
+        );
+
+        texture.create_view(&wgpu::TextureViewDescriptor::default())
+    };
+
+    let color_view = create_view(colors, wgpu::TextureFormat::Rgba8UnormSrgb);
+    let normal_view = create_view(normals, wgpu::TextureFormat::Rgba8Unorm);
+
+    // Nearest filtering keeps the texels sharp, and keeps neighbouring textures from blending together
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: None,
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Nearest,
+        min_filter: wgpu::FilterMode::Nearest,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    } );
+
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&color_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&normal_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            }
+        ],
+        label: None
+    } )
+}
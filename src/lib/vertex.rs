@@ -1,17 +1,71 @@
+use cgmath::{
+    Matrix,
+    Matrix3,
+    Matrix4,
+    SquareMatrix
+};
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     pub position: [f32; 3],
-    pub color: [f32; 3],
-    pub normal: [f32; 3]
+    // Triangles with any vertex that has an alpha below 1 are drawn in the transparent pass
+    pub color: [f32; 4],
+    pub normal: [f32; 3],
+    // Baked light from the world's voxel lighting, which the world fills in for tiles
+    pub light: [f32; 3],
+    // Brightness left after ambient occlusion, which the world also fills in for tiles
+    pub ao: f32,
+    // Position in the world's texture atlas, or `Vertex::UNTEXTURED`
+    pub uv: [f32; 2],
+    // Index into the world's materials, which the world fills in for tiles
+    // Meshes take their material from the entity instead, so this is ignored for them
+    pub material: u32,
+    // Color given off by tiles that emit light, in 8 bits per channel, which the world also fills in
+    pub emissive: [u8; 4]
+}
+
+// White, untextured and unlit, with the fields that the world fills in left for it to fill
+impl Default for Vertex {
+    fn default() -> Self {
+        Self {
+            position: [0.0; 3],
+            color: [1.0; 4],
+            normal: [0.0; 3],
+            light: [0.0; 3],
+            ao: 1.0,
+            uv: Self::UNTEXTURED,
+            material: 0,
+            emissive: [0; 4]
+        }
+    }
 }
 
 impl Vertex {
-    const ATTRIBUTES: [wgpu::VertexAttribute; 3] = { 
+    // Texture coordinates of vertices that are only colored
+    pub const UNTEXTURED: [f32; 2] = [-1.0, -1.0];
+
+    // An opaque, untextured vertex
+    // Other fields can be set with `Vertex { uv, ..Vertex::new(position, color, normal) }`
+    pub fn new(position: [f32; 3], color: [f32; 3], normal: [f32; 3]) -> Self {
+        Self {
+            position,
+            color: [color[0], color[1], color[2], 1.0],
+            normal,
+            ..Default::default()
+        }
+    }
+
+    // Light and occlusion sit next to each other and are read as a single attribute, and so are the material and emissive color
+    // This keeps the vertex and instance attributes together within the limit of 16
+    const ATTRIBUTES: [wgpu::VertexAttribute; 6] = { 
         wgpu::vertex_attr_array![
             0 => Float32x3, 
-            1 => Float32x3, 
-            2 => Float32x3
+            1 => Float32x4, 
+            2 => Float32x3, 
+            3 => Float32x4, 
+            4 => Float32x2,
+            5 => Uint32x2
         ] 
     };
 
@@ -24,4 +78,99 @@ impl Vertex {
             attributes: &Self::ATTRIBUTES,
         }
     }
-}
\ No newline at end of file
+}
+
+// Corner of a camera-facing particle quad, which is expanded in the shader
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ParticleVertex {
+    pub(crate) center: [f32; 3],
+    pub(crate) corner: [f32; 2],
+    pub(crate) size: f32,
+    pub(crate) color: [f32; 3]
+}
+
+impl ParticleVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] = { 
+        wgpu::vertex_attr_array![
+            0 => Float32x3, 
+            1 => Float32x2, 
+            2 => Float32, 
+            3 => Float32x3
+        ] 
+    };
+
+    pub(crate) fn description<'a>() -> wgpu::VertexBufferLayout<'a> {
+        use std::mem;
+
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+// Places a mesh in the world, along with the inverse transpose that is used to transform its normals
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct Instance {
+    pub(crate) model: [[f32; 4]; 4],
+    pub(crate) normal: [[f32; 3]; 3],
+    pub(crate) color: [f32; 4],
+    // Voxel light at the entity, since meshes don't have any baked in
+    pub(crate) light: [f32; 3],
+    // The entity's material and the color it glows with, since meshes are shared between entities
+    pub(crate) material: u32,
+    pub(crate) emissive: [u8; 4]
+}
+
+impl Default for Instance {
+    fn default() -> Self {
+        Self::new(Matrix4::identity(), [1.0; 4], [0.0; 3], 0, [0; 4])
+    }
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 10] = { 
+        wgpu::vertex_attr_array![
+            6 => Float32x4, 
+            7 => Float32x4, 
+            8 => Float32x4, 
+            9 => Float32x4, 
+            10 => Float32x3, 
+            11 => Float32x3, 
+            12 => Float32x3, 
+            13 => Float32x4, 
+            14 => Float32x3, 
+            15 => Uint32x2
+        ] 
+    };
+
+    pub(crate) fn new(model: Matrix4<f32>, color: [f32; 4], light: [f32; 3], material: u32, emissive: [u8; 4]) -> Self {
+        let linear = Matrix3::from_cols(model.x.truncate(), model.y.truncate(), model.z.truncate());
+        let normal = linear
+            .invert()
+            .unwrap_or_else(Matrix3::identity)
+            .transpose();
+
+        Self {
+            model: model.into(),
+            normal: normal.into(),
+            color,
+            light,
+            material,
+            emissive
+        }
+    }
+
+    pub(crate) fn description<'a>() -> wgpu::VertexBufferLayout<'a> {
+        use std::mem;
+
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashSet;
+
+use cgmath::{
+    Point3,
+    Vector3,
+    EuclideanSpace
+};
+
+use crate::vertex::Vertex;
+
+use super::World;
+
+// Brightness of a face corner with each number of open neighbours, out of three
+const OCCLUSION_CURVE: [f32; 4] = [0.45, 0.65, 0.85, 1.0];
+
+// Past this many tile changes in a tick, every face is checked instead of the ones around each change
+const MAX_INCREMENTAL_CHANGES: usize = 64;
+
+// How far a position can be from a half-tile boundary and still count as a tile corner
+const CORNER_TOLERANCE: f32 = 0.001;
+
+impl<'a> World<'a> {
+    pub fn ambient_occlusion(&self) -> bool {
+        self.ambient_occlusion
+    }
+
+    // Darkens the corners of tile faces by how many solid tiles surround them
+    pub fn set_ambient_occlusion(&mut self, enabled: bool) {
+        self.ambient_occlusion = enabled;
+        self.rebuild_tile_geometry();
+    }
+
+    // Updates the faces next to the tiles that changed since the last tick
+    // Tiles that were added already have their own faces occluded, but their neighbours don't
+    pub(crate) fn resolve_ambient_occlusion(&mut self) {
+        let revision = self.tile_revision();
+        if !self.ambient_occlusion || self.occlusion_revision == revision {
+            self.occlusion_revision = revision;
+            return;
+        }
+
+        let changes = self
+            .tile_changes_since(self.occlusion_revision)
+            .map(|changes| changes.map(|position| position.cast::<i32>().unwrap()).collect::<HashSet<_>>());
+        self.occlusion_revision = revision;
+
+        // Changes that the world no longer remembers could be anywhere
+        let everything = changes.as_ref().is_none_or(|changes| changes.len() > MAX_INCREMENTAL_CHANGES);
+        let changes = changes.unwrap_or_default();
+        let near_change = |cell: Point3<i32>| {
+            everything || (-1..=1).any(|x| (-1..=1).any(|y| (-1..=1).any(|z| {
+                changes.contains(&(cell + Vector3::new(x, y, z)))
+            } )))
+        };
+
+        // Each tile's triangles are paired up on their own, so that a tile with an odd number of them
+        // doesn't throw off the pairs of the tiles after it
+        let mut vertices = std::mem::take(&mut self.tile_vertices);
+        let mut indices = std::mem::take(&mut self.tile_indices);
+        let changes = self.tile_ranges
+            .values()
+            .filter_map(|range| {
+                self.occlude_quads(&mut vertices, &mut indices[range.indices.clone()], near_change)
+                    .map(|(first_vertex, first_index)| (first_vertex, range.indices.start + first_index))
+            } )
+            .collect::<Vec<_>>();
+        self.tile_vertices = vertices;
+        self.tile_indices = indices;
+
+        for (first_vertex, first_index) in changes {
+            self.mark_tile_geometry_changed(first_vertex, first_index);
+        }
+    }
+
+    // Works out the occlusion of every face corner in a tile's own geometry, before it joins the rest
+    pub(crate) fn occlude_vertices(&self, vertices: &mut [Vertex], indices: &mut [u32]) {
+        for vertex in vertices.iter_mut() {
+            vertex.ao = 1.0;
+        }
+
+        if self.ambient_occlusion {
+            self.occlude_quads(vertices, indices, |_| true);
+        }
+    }
+
+    // Occludes the corners of each quad whose front cell passes the filter, and picks the diagonal it is split along
+    // Quads are pairs of triangles in a row that share an edge and lie on the face of a tile,
+    // and a triangle that doesn't pair up with the next one is skipped on its own
+    // Returns the first vertex and index that changed, if any did
+    fn occlude_quads(
+        &self,
+        vertices: &mut [Vertex],
+        indices: &mut [u32],
+        filter: impl Fn(Point3<i32>) -> bool
+    ) -> Option<(usize, usize)> {
+        let mut first_vertex = None;
+        let mut first_index = None;
+
+        let mut start = 0;
+        while start + 6 <= indices.len() {
+            let quad_start = start;
+            let quad_indices = &mut indices[quad_start..quad_start + 6];
+
+            // The two triangles are turned so that the shared edge comes last in the first and first in the second
+            let (corners, shared) = match quad_corners(quad_indices) {
+                Some(corners) => corners,
+                None => {
+                    start += 3;
+                    continue
+                }
+            };
+            start += 6;
+
+            let quad_vertices = corners.map(|index| &vertices[index as usize]);
+            let front = match self.quad_front_cell(quad_vertices) {
+                Some(front) if filter(front) => front,
+                _ => continue
+            };
+
+            let center = quad_vertices
+                .iter()
+                .fold(Vector3::new(0.0, 0.0, 0.0), |sum, vertex| sum + Vector3::from(vertex.position))
+                / 4.0;
+
+            // Faces that look into a solid tile are hidden, and are left unoccluded for when they are uncovered
+            let hidden = front.cast::<i16>().is_none_or(|cell| self.is_solid(cell));
+            let occlusion = corners.map(|index| if hidden { 3 } else {
+                self.corner_occlusion(front, Vector3::from(vertices[index as usize].position) - center)
+            } );
+
+            for (corner, index) in corners.iter().enumerate() {
+                let vertex = &mut vertices[*index as usize];
+                if vertex.ao != OCCLUSION_CURVE[occlusion[corner]] {
+                    vertex.ao = OCCLUSION_CURVE[occlusion[corner]];
+                    first_vertex = Some(first_vertex.map_or(*index as usize, |first: usize| first.min(*index as usize)));
+                }
+            }
+
+            // Splitting along the brighter diagonal keeps a dark corner from bleeding across the whole quad
+            // The corners go around the quad, and `shared` is the diagonal that it is split along now
+            let [a, b, c, d] = occlusion;
+            let split = match (a + c).cmp(&(b + d)) {
+                std::cmp::Ordering::Greater => 0,
+                std::cmp::Ordering::Less => 1,
+                std::cmp::Ordering::Equal => shared
+            };
+            if split != shared {
+                let [a, b, c, d] = if split == 0 { corners } else { [corners[1], corners[2], corners[3], corners[0]] };
+                quad_indices.copy_from_slice(&[a, b, c, a, c, d]);
+                first_index.get_or_insert(quad_start);
+            }
+        }
+
+        match (first_vertex, first_index) {
+            (None, None) => None,
+            (vertex, index) => Some((vertex.unwrap_or(vertices.len()), index.unwrap_or(indices.len())))
+        }
+    }
+
+    // The cell that a quad on the face of a tile looks out into
+    // Quads that aren't flat against the tile grid have no front cell
+    fn quad_front_cell(&self, vertices: [&Vertex; 4]) -> Option<Point3<i32>> {
+        let normal = Vector3::from(vertices[0].normal);
+        let axis = (0..3).find(|axis| normal[*axis].abs() > 0.99)?;
+
+        let on_grid = vertices.iter().all(|vertex| {
+            vertex.normal == vertices[0].normal
+                && vertex.position.iter().all(|value| ((value + 0.5) - (value + 0.5).round()).abs() < CORNER_TOLERANCE)
+        } );
+
+        if !on_grid {
+            return None;
+        }
+
+        let center = vertices
+            .iter()
+            .fold(Point3::origin(), |sum, vertex| sum + Vector3::from(vertex.position))
+            / 4.0;
+
+        let mut front = center;
+        front[axis] += normal[axis].signum() * 0.5;
+
+        Some(Point3::new(front.x.round() as i32, front.y.round() as i32, front.z.round() as i32))
+    }
+
+    // Counts the open cells among the two sides and the corner next to a corner of a face
+    // The corner is dark when both sides are solid, whether or not the cell between them is
+    fn corner_occlusion(&self, front: Point3<i32>, towards_corner: Vector3<f32>) -> usize {
+        let step = |axis: usize| {
+            let mut offset = Vector3::new(0, 0, 0);
+            if towards_corner[axis].abs() > CORNER_TOLERANCE {
+                offset[axis] = towards_corner[axis].signum() as i32;
+            }
+            offset
+        };
+
+        // Only the two axes that run along the face are non-zero
+        let offsets = [0, 1, 2]
+            .map(step)
+            .into_iter()
+            .filter(|offset| *offset != Vector3::new(0, 0, 0))
+            .collect::<Vec<_>>();
+
+        if offsets.len() != 2 {
+            return 3;
+        }
+
+        let solid = |offset: Vector3<i32>| {
+            (front + offset)
+                .cast::<i16>()
+                .is_some_and(|cell| self.is_solid(cell))
+        };
+
+        let side_a = solid(offsets[0]);
+        let side_b = solid(offsets[1]);
+        if side_a && side_b {
+            return 0;
+        }
+
+        3 - side_a as usize - side_b as usize - solid(offsets[0] + offsets[1]) as usize
+    }
+}
+
+// Finds the four corners of a pair of triangles that share an edge, in order around the quad,
+// along with which of the two diagonals the quad is currently split along
+fn quad_corners(indices: &[u32]) -> Option<([u32; 4], usize)> {
+    let (first, second) = indices.split_at(3);
+
+    for turn in 0..3 {
+        let [a, b, c] = [first[turn], first[(turn + 1) % 3], first[(turn + 2) % 3]];
+
+        // The second triangle goes along the shared edge the other way
+        for other in 0..3 {
+            if second[other] == c && second[(other + 1) % 3] == b {
+                let d = second[(other + 2) % 3];
+                if [a, b, c].contains(&d) {
+                    return None;
+                }
+
+                // Going around the quad: a, b, d, c, split from b to c
+                return Some(([a, b, d, c], 1));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Point3;
+
+    use crate::vertex::Vertex;
+    use crate::world::{ World, Drawable, Tile, Triangles };
+
+    // A solid tile that only draws its top face, or a single triangle
+    struct TestTile {
+        position: Point3<i16>,
+        lone_triangle: bool
+    }
+
+    impl Drawable for TestTile {
+        fn center(&self) -> Point3<f32> { self.position.cast().unwrap() }
+        fn color(&self) -> [f32; 3] { [1.0; 3] }
+        fn light(&self) -> Option<[f32; 4]> { None }
+        fn set_center(&mut self, _: Point3<f32>) {}
+        fn set_color(&mut self, _: [f32; 3]) {}
+        fn set_light(&mut self, _: [f32; 4]) {}
+    }
+
+    impl Tile for TestTile {
+        fn position(&self) -> Point3<i16> { self.position }
+        fn set_position(&mut self, position: Point3<i16>) { self.position = position; }
+
+        fn build_object_data(&self) -> Triangles {
+            let center = self.center();
+            let corner = |x: f32, z: f32| Vertex::new([center.x + x, center.y + 0.5, center.z + z], [1.0; 3], [0.0, 1.0, 0.0]);
+
+            if self.lone_triangle {
+                Triangles { vertices: vec![corner(-0.5, -0.5), corner(-0.5, 0.5), corner(0.5, 0.5)], indices: vec![0, 1, 2] }
+            } else {
+                Triangles {
+                    vertices: vec![corner(-0.5, -0.5), corner(-0.5, 0.5), corner(0.5, 0.5), corner(0.5, -0.5)],
+                    indices: vec![0, 1, 2, 0, 2, 3]
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tile_with_odd_triangles_leaves_later_tiles_occluded() {
+        let mut world = World::default();
+        world.set_ambient_occlusion(true);
+
+        world.add_tile(TestTile { position: Point3::new(-4, 0, 0), lone_triangle: true });
+        world.add_tile(TestTile { position: Point3::new(0, 0, 0), lone_triangle: false });
+        world.resolve_ambient_occlusion();
+
+        // A tile next to the cell above the face darkens the two corners on its side
+        world.add_tile(TestTile { position: Point3::new(1, 1, 0), lone_triangle: true });
+        world.resolve_ambient_occlusion();
+
+        let (vertices, indices) = world.tile_geometry();
+        let face = indices
+            .iter()
+            .map(|index| &vertices[*index as usize])
+            .filter(|vertex| vertex.position[1] == 0.5 && vertex.position[0].abs() <= 0.5)
+            .collect::<Vec<_>>();
+
+        assert!(!face.is_empty());
+        for vertex in face {
+            assert_eq!(vertex.ao < 1.0, vertex.position[0] > 0.0, "corner at {:?}", vertex.position);
+        }
+    }
+}
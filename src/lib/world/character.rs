@@ -0,0 +1,195 @@
+use cgmath::{
+    Vector3,
+    InnerSpace,
+    Zero
+};
+
+use super::{
+    World,
+    EntityHandle,
+    physics::vertical_axis
+};
+
+// Distance a character is moved onto a ledge when stepping up
+const STEP_PROBE_DISTANCE: f32 = 0.05;
+
+// Tunable movement settings, all in tiles and ticks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CharacterConfig {
+    // Horizontal speed that movement input can accelerate the character to
+    pub max_speed: f32,
+    // Horizontal velocity gained on each tick of movement input while grounded
+    pub acceleration: f32,
+    // Fraction of the acceleration available while in the air
+    pub air_control: f32,
+    // Upward impulse applied when jumping
+    pub jump_impulse: f32,
+    // Number of ticks after leaving the ground during which the character can still jump
+    pub coyote_ticks: u32,
+    // Tallest ledge the character steps onto automatically
+    pub step_height: f32,
+    // Steepest ground, in radians, that the character can stand on
+    // Characters on steeper ground slide down it and can't jump
+    pub slope_limit: f32
+}
+
+impl Default for CharacterConfig {
+    fn default() -> Self {
+        Self {
+            max_speed: 0.3,
+            acceleration: 0.05,
+            air_control: 0.3,
+            jump_impulse: 0.4,
+            coyote_ticks: 6,
+            step_height: 1.05,
+            slope_limit: 50.0_f32.to_radians()
+        }
+    }
+}
+
+// Drives an entity from movement input
+// Should be updated once per tick, during the game update
+// Up is the direction opposite to the world's gravity, and horizontal means perpendicular to it
+#[derive(Clone, Debug)]
+pub struct CharacterController {
+    pub config: CharacterConfig,
+    movement: Vector3<f32>,
+    jump_requested: bool,
+    grounded: bool,
+    ground_normal: Option<Vector3<f32>>,
+    ticks_since_grounded: u32
+}
+
+impl CharacterController {
+    pub fn new(config: CharacterConfig) -> Self {
+        Self {
+            config,
+            movement: Vector3::zero(),
+            jump_requested: false,
+            grounded: false,
+            ground_normal: None,
+            // Characters that start in the air can't jump until they land
+            ticks_since_grounded: u32::MAX
+        }
+    }
+
+    // Horizontal direction to move in, where a length of 1 is full speed
+    // Any vertical part is ignored
+    pub fn set_movement(&mut self, movement: Vector3<f32>) {
+        self.movement = movement;
+    }
+
+    // The character jumps during the next update, if it is able to
+    pub fn jump(&mut self) {
+        self.jump_requested = true;
+    }
+
+    // Whether the character was standing on walkable ground during the last update
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    // Normal of the ground beneath the character, which may be too steep to stand on
+    pub fn ground_normal(&self) -> Option<Vector3<f32>> {
+        self.ground_normal
+    }
+
+    pub fn update(&mut self, world: &mut World, handle: &EntityHandle) {
+        let mut handle = handle.clone();
+
+        let (mut center, mut velocity, collider, collisions) = {
+            let entity = handle.borrow();
+            (entity.center(), entity.velocity(), entity.collider(), entity.collisions())
+        };
+
+        let up = world.up();
+        let vertical = vertical_axis(up);
+        let horizontal = |vector: Vector3<f32>| vector - up * vector.dot(up);
+
+        // The entity must have landed on something during the last tick
+        self.ground_normal = if collisions[vertical] && velocity.dot(up) <= 0.0 {
+            world.ground_normal(&collider, center)
+        } else {
+            None
+        };
+
+        let steep = self.ground_normal.filter(|normal| normal.dot(up) < self.config.slope_limit.cos());
+        self.grounded = self.ground_normal.is_some() && steep.is_none();
+
+        self.ticks_since_grounded = if self.grounded {
+            0
+        } else {
+            self.ticks_since_grounded.saturating_add(1)
+        };
+
+        let mut movement = horizontal(self.movement);
+        if movement.magnitude2() > 1.0 {
+            movement = movement.normalize();
+        }
+
+        if let Some(normal) = steep {
+            // Characters can't walk up slopes that are too steep, and slide back down them
+            let uphill = -horizontal(normal).normalize();
+            movement -= uphill * movement.dot(uphill).max(0.0);
+
+            let gravity = world.physics_config().gravity;
+            velocity += gravity - normal * gravity.dot(normal);
+        }
+
+        // Movement input never pushes the character past its maximum speed,
+        // but it doesn't slow down characters that were launched by something else
+        let acceleration = if self.grounded {
+            self.config.acceleration
+        } else {
+            self.config.acceleration * self.config.air_control
+        };
+
+        let sliding = horizontal(velocity);
+        let mut accelerated = sliding + movement * acceleration;
+
+        let speed_limit = sliding.magnitude().max(self.config.max_speed);
+        if accelerated.magnitude() > speed_limit {
+            accelerated = accelerated.normalize_to(speed_limit);
+        }
+
+        velocity += accelerated - sliding;
+
+        // Climb onto ledges that block the character's movement
+        if self.grounded {
+            for axis in (0..3).filter(|axis| *axis != vertical) {
+                if !collisions[axis] || movement[axis] == 0.0 { continue; }
+
+                let distance = STEP_PROBE_DISTANCE * movement[axis].signum();
+                if let Some(lift) = world.find_climb_height(&collider, center, axis, distance, up, self.config.step_height) {
+                    center[axis] += distance;
+                    center += up * lift;
+                }
+            }
+        }
+
+        // Jumping is allowed for a short time after walking off a ledge
+        let jumping = self.jump_requested && self.ticks_since_grounded <= self.config.coyote_ticks;
+        if jumping {
+            velocity -= up * velocity.dot(up).min(0.0);
+            self.ticks_since_grounded = u32::MAX;
+        }
+
+        {
+            let mut entity = handle.borrow_mut();
+            entity.set_center(center);
+            entity.set_velocity(velocity);
+        }
+
+        if jumping {
+            world.apply_impulse(&handle, up * self.config.jump_impulse);
+        }
+
+        self.jump_requested = false;
+    }
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        Self::new(CharacterConfig::default())
+    }
+}
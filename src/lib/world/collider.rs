@@ -0,0 +1,403 @@
+use cgmath::{
+    Point3,
+    Vector3,
+    InnerSpace,
+    Zero
+};
+
+use crate::vertex::Vertex;
+
+use super::drawable::Triangles;
+
+// Contacts closer than this are treated as touching rather than overlapping
+const CONTACT_EPSILON: f32 = 0.001;
+
+// Thickness of the edges drawn by the debug visualisation
+const DEBUG_EDGE_WIDTH: f32 = 0.02;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Collider {
+    // Axis-aligned box, extending `half_extents` in each direction from the center
+    Aabb { half_extents: Vector3<f32> },
+    Sphere { radius: f32 },
+    // Vertical capsule, the segment between the two hemispheres is `2 * half_height` long
+    Capsule { radius: f32, half_height: f32 },
+}
+
+// How an entity or tile interacts with the entities that touch it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CollisionMode {
+    // Blocks other entities, and is pushed by them if it can move
+    #[default]
+    Solid,
+    // Reports overlaps, but never blocks or pushes
+    Trigger,
+    // Ignores entities entirely
+    Ghost,
+}
+
+// Result of a collision between two shapes
+#[derive(Clone, Copy, Debug)]
+pub struct Contact {
+    // Points from the first shape towards the second
+    pub normal: Vector3<f32>,
+    pub depth: f32
+}
+
+impl Default for Collider {
+    // A box with no volume, which collides as a single point
+    fn default() -> Self {
+        Self::Aabb { half_extents: Vector3::zero() }
+    }
+}
+
+impl Collider {
+    pub fn half_extents(&self) -> Vector3<f32> {
+        match *self {
+            Self::Aabb { half_extents } => half_extents,
+            Self::Sphere { radius } => Vector3::new(radius, radius, radius),
+            Self::Capsule { radius, half_height } => {
+                Vector3::new(radius, radius + half_height, radius)
+            }
+        }
+    }
+
+    pub fn bounds(&self, center: Point3<f32>) -> (Point3<f32>, Point3<f32>) {
+        let half_extents = self.half_extents();
+
+        (center - half_extents, center + half_extents)
+    }
+
+    // Distance between the surface of the shape and a point, negative when the point is inside
+    pub fn distance_to_point(&self, center: Point3<f32>, point: Point3<f32>) -> f32 {
+        match *self {
+            Self::Aabb { half_extents } => {
+                let offset = point - center;
+                let outside = Vector3::new(
+                    (offset.x.abs() - half_extents.x).max(0.0),
+                    (offset.y.abs() - half_extents.y).max(0.0),
+                    (offset.z.abs() - half_extents.z).max(0.0)
+                );
+
+                let inside = (offset.x.abs() - half_extents.x)
+                    .max(offset.y.abs() - half_extents.y)
+                    .max(offset.z.abs() - half_extents.z)
+                    .min(0.0);
+
+                outside.magnitude() + inside
+            },
+            Self::Sphere { radius } => (point - center).magnitude() - radius,
+            Self::Capsule { radius, half_height } => {
+                let mut offset = point - center;
+                offset.y -= offset.y.clamp(-half_height, half_height);
+
+                offset.magnitude() - radius
+            }
+        }
+    }
+
+    // Whether the shape overlaps the box between `min` and `max`
+    // Shapes that are only touching the box do not count
+    pub fn intersects_box(
+        &self,
+        center: Point3<f32>,
+        min: Point3<f32>,
+        max: Point3<f32>
+    ) -> bool {
+        match *self {
+            Self::Aabb { .. } => {
+                let (shape_min, shape_max) = self.bounds(center);
+
+                shape_min.x < max.x - CONTACT_EPSILON && shape_max.x > min.x + CONTACT_EPSILON &&
+                shape_min.y < max.y - CONTACT_EPSILON && shape_max.y > min.y + CONTACT_EPSILON &&
+                shape_min.z < max.z - CONTACT_EPSILON && shape_max.z > min.z + CONTACT_EPSILON
+            },
+            Self::Sphere { radius } => {
+                Self::segment_box_distance(center, 0.0, min, max) < radius - CONTACT_EPSILON
+            },
+            Self::Capsule { radius, half_height } => {
+                Self::segment_box_distance(center, half_height, min, max) < radius - CONTACT_EPSILON
+            }
+        }
+    }
+
+    // Finds the overlap between this shape and another one
+    pub fn contact(
+        &self,
+        center: Point3<f32>,
+        other: &Collider,
+        other_center: Point3<f32>
+    ) -> Option<Contact> {
+        match (*self, *other) {
+            (Self::Aabb { half_extents }, Self::Aabb { half_extents: other_half_extents }) => {
+                let offset = other_center - center;
+
+                let mut contact: Option<Contact> = None;
+                for axis in 0..3 {
+                    let depth = half_extents[axis] + other_half_extents[axis] - offset[axis].abs();
+                    if depth <= CONTACT_EPSILON { return None; }
+
+                    if contact.is_none_or(|c| depth < c.depth) {
+                        let mut normal = Vector3::zero();
+                        normal[axis] = if offset[axis] < 0.0 { -1.0 } else { 1.0 };
+
+                        contact = Some(Contact { normal, depth });
+                    }
+                }
+
+                contact
+            },
+            (Self::Aabb { .. }, _) => {
+                other.contact(other_center, self, center).map(|contact| Contact {
+                    normal: -contact.normal,
+                    depth: contact.depth
+                } )
+            },
+            (_, Self::Aabb { half_extents }) => {
+                let (half_height, radius) = self.segment();
+
+                // Point on the segment closest to the box
+                let y = closest_in_range(
+                    center.y - half_height, center.y + half_height,
+                    other_center.y - half_extents.y, other_center.y + half_extents.y
+                );
+                let point = Point3::new(center.x, y, center.z);
+
+                let closest = Point3::new(
+                    point.x.clamp(other_center.x - half_extents.x, other_center.x + half_extents.x),
+                    point.y.clamp(other_center.y - half_extents.y, other_center.y + half_extents.y),
+                    point.z.clamp(other_center.z - half_extents.z, other_center.z + half_extents.z)
+                );
+
+                let offset = closest - point;
+                let distance = offset.magnitude();
+                if distance > 0.0 {
+                    if distance >= radius - CONTACT_EPSILON { return None; }
+
+                    return Some(Contact { normal: offset / distance, depth: radius - distance });
+                }
+
+                // The segment passes through the box, push out along the shallowest axis
+                let mut contact: Option<Contact> = None;
+                for axis in 0..3 {
+                    let inside = point[axis] - other_center[axis];
+                    let depth = half_extents[axis] - inside.abs() + radius;
+
+                    if contact.is_none_or(|c| depth < c.depth) {
+                        let mut normal = Vector3::zero();
+                        normal[axis] = if inside > 0.0 { -1.0 } else { 1.0 };
+
+                        contact = Some(Contact { normal, depth });
+                    }
+                }
+
+                contact
+            },
+            _ => {
+                let (half_height, radius) = self.segment();
+                let (other_half_height, other_radius) = other.segment();
+
+                let y = closest_in_range(
+                    center.y - half_height, center.y + half_height,
+                    other_center.y - other_half_height, other_center.y + other_half_height
+                );
+                let other_y = closest_in_range(
+                    other_center.y - other_half_height, other_center.y + other_half_height,
+                    center.y - half_height, center.y + half_height
+                );
+
+                let offset = Point3::new(other_center.x, other_y, other_center.z) 
+                    - Point3::new(center.x, y, center.z);
+                let distance = offset.magnitude();
+                if distance >= radius + other_radius - CONTACT_EPSILON { return None; }
+
+                let normal = if distance > 0.0 { offset / distance } else { Vector3::unit_y() };
+
+                Some(Contact { normal, depth: radius + other_radius - distance })
+            }
+        }
+    }
+
+    // Half height and radius of a rounded shape's core segment
+    fn segment(&self) -> (f32, f32) {
+        match *self {
+            Self::Sphere { radius } => (0.0, radius),
+            Self::Capsule { radius, half_height } => (half_height, radius),
+            Self::Aabb { .. } => unreachable!()
+        }
+    }
+
+    // Whether any part of the shape lies behind the plane through `point`
+    pub fn penetrates_plane(
+        &self,
+        center: Point3<f32>,
+        point: Point3<f32>,
+        normal: Vector3<f32>
+    ) -> bool {
+        // The point of the shape furthest behind the plane
+        let support = match *self {
+            Self::Aabb { half_extents } => center - Vector3::new(
+                half_extents.x * normal.x.signum(),
+                half_extents.y * normal.y.signum(),
+                half_extents.z * normal.z.signum()
+            ),
+            Self::Sphere { radius } => center - normal * radius,
+            Self::Capsule { radius, half_height } => {
+                center - Vector3::unit_y() * (half_height * normal.y.signum()) - normal * radius
+            }
+        };
+
+        (support - point).dot(normal) < -CONTACT_EPSILON
+    }
+
+    // Distance between a box and the vertical segment through `center`
+    fn segment_box_distance(
+        center: Point3<f32>,
+        half_height: f32,
+        min: Point3<f32>,
+        max: Point3<f32>
+    ) -> f32 {
+        let gap = Vector3::new(
+            (min.x - center.x).max(center.x - max.x).max(0.0),
+            (min.y - (center.y + half_height)).max((center.y - half_height) - max.y).max(0.0),
+            (min.z - center.z).max(center.z - max.z).max(0.0)
+        );
+
+        gap.magnitude()
+    }
+
+    // Builds a wireframe outline of the shape for debugging
+    pub(crate) fn build_debug_data(&self, center: Point3<f32>, color: [f32; 3]) -> Triangles {
+        let mut triangles = Triangles { vertices: Vec::new(), indices: Vec::new() };
+
+        match *self {
+            Self::Aabb { half_extents } => {
+                let (min, max) = self.bounds(center);
+                let corner = |i: usize| Point3::new(
+                    if i & 1 == 0 { min.x } else { max.x },
+                    if i & 2 == 0 { min.y } else { max.y },
+                    if i & 4 == 0 { min.z } else { max.z }
+                );
+
+                // A point collider still gets a small marker
+                if half_extents.is_zero() {
+                    for axis in [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()] {
+                        push_edge(&mut triangles, center - axis * 0.1, center + axis * 0.1, color);
+                    }
+                }
+
+                for i in 0..8 {
+                    for bit in [1, 2, 4] {
+                        if i & bit == 0 {
+                            push_edge(&mut triangles, corner(i), corner(i | bit), color);
+                        }
+                    }
+                }
+            },
+            Self::Sphere { radius } => {
+                push_ring(&mut triangles, center, radius, Vector3::unit_x(), Vector3::unit_z(), color);
+                push_ring(&mut triangles, center, radius, Vector3::unit_x(), Vector3::unit_y(), color);
+                push_ring(&mut triangles, center, radius, Vector3::unit_z(), Vector3::unit_y(), color);
+            },
+            Self::Capsule { radius, half_height } => {
+                let top = center + Vector3::unit_y() * half_height;
+                let bottom = center - Vector3::unit_y() * half_height;
+
+                push_ring(&mut triangles, top, radius, Vector3::unit_x(), Vector3::unit_z(), color);
+                push_ring(&mut triangles, bottom, radius, Vector3::unit_x(), Vector3::unit_z(), color);
+
+                for side in [Vector3::unit_x(), Vector3::unit_z()] {
+                    push_edge(&mut triangles, top + side * radius, bottom + side * radius, color);
+                    push_edge(&mut triangles, top - side * radius, bottom - side * radius, color);
+
+                    push_arc(&mut triangles, top, radius, side, Vector3::unit_y(), color);
+                    push_arc(&mut triangles, bottom, radius, side, -Vector3::unit_y(), color);
+                }
+            }
+        }
+
+        triangles
+    }
+}
+
+// Value within `min..=max` closest to the range `other_min..=other_max`
+fn closest_in_range(min: f32, max: f32, other_min: f32, other_max: f32) -> f32 {
+    let (low, high) = (min.max(other_min), max.min(other_max));
+
+    if low <= high {
+        (low + high) * 0.5
+    } else if max < other_min {
+        max
+    } else {
+        min
+    }
+}
+
+const DEBUG_RING_SEGMENTS: usize = 16;
+
+// Full circle in the plane spanned by `u` and `v`
+fn push_ring(
+    triangles: &mut Triangles,
+    center: Point3<f32>,
+    radius: f32,
+    u: Vector3<f32>,
+    v: Vector3<f32>,
+    color: [f32; 3]
+) {
+    let point = |i: usize| {
+        let angle = std::f32::consts::TAU * i as f32 / DEBUG_RING_SEGMENTS as f32;
+        center + (u * angle.cos() + v * angle.sin()) * radius
+    };
+
+    for i in 0..DEBUG_RING_SEGMENTS {
+        push_edge(triangles, point(i), point(i + 1), color);
+    }
+}
+
+// Half circle from `-u` to `u`, bulging towards `v`
+fn push_arc(
+    triangles: &mut Triangles,
+    center: Point3<f32>,
+    radius: f32,
+    u: Vector3<f32>,
+    v: Vector3<f32>,
+    color: [f32; 3]
+) {
+    let segments = DEBUG_RING_SEGMENTS / 2;
+    let point = |i: usize| {
+        let angle = std::f32::consts::PI * i as f32 / segments as f32;
+        center + (u * angle.cos() + v * angle.sin()) * radius
+    };
+
+    for i in 0..segments {
+        push_edge(triangles, point(i), point(i + 1), color);
+    }
+}
+
+// Thin square prism between two points
+fn push_edge(triangles: &mut Triangles, start: Point3<f32>, end: Point3<f32>, color: [f32; 3]) {
+    let direction = end - start;
+    if direction.is_zero() { return; }
+
+    let direction = direction.normalize();
+    let helper = if direction.y.abs() < 0.9 { Vector3::unit_y() } else { Vector3::unit_x() };
+    let side = direction.cross(helper).normalize() * (DEBUG_EDGE_WIDTH * 0.5);
+    let up = direction.cross(side);
+
+    let corners = [side + up, side - up, -side - up, -side + up];
+    for face in 0..4 {
+        let a = corners[face];
+        let b = corners[(face + 1) % 4];
+        let normal = (a + b).normalize();
+
+        let offset = triangles.vertices.len() as u32;
+        for position in [start + a, start + b, end + b, end + a] {
+            triangles.vertices.push(Vertex::new(position.into(), color, normal.into()));
+        }
+
+        triangles.indices.extend_from_slice(&[
+            offset, offset + 2, offset + 1,
+            offset, offset + 3, offset + 2
+        ]);
+    }
+}
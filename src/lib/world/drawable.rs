@@ -2,6 +2,8 @@ use cgmath::Point3;
 
 use crate::vertex::Vertex;
 
+use super::Material;
+
 pub struct Triangles {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>
@@ -12,9 +14,17 @@ pub trait Drawable {
     fn color(&self) -> [f32; 3];
     fn light(&self) -> Option<[f32; 4]>;
 
+    // Entities with an alpha below 1 are drawn in the transparent pass
+    // Tiles set the alpha of their own vertices instead
+    fn alpha(&self) -> f32 {
+        1.0
+    }
+
+    fn material(&self) -> Material {
+        Material::default()
+    }
+
     fn set_center(&mut self, center: Point3<f32>);
     fn set_color(&mut self, color: [f32; 3]);
     fn set_light(&mut self, light: [f32; 4]);
-    
-    fn build_object_data(&self) -> Triangles;
 }
\ No newline at end of file
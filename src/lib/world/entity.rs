@@ -4,18 +4,42 @@ use std::{
     ops::Deref
 };
 
-use cgmath::Vector3;
+use cgmath::{
+    Vector3, 
+    Matrix4, 
+    EuclideanSpace
+};
 
-use super::drawable;
+use super::{ 
+    drawable, 
+    collider::{ Collider, CollisionMode }, 
+    mesh::MeshId 
+};
 
 pub trait Entity: drawable::Drawable {
     fn velocity(&self) -> Vector3<f32>;
     fn collisions(&self) -> Vector3<bool>;
-    fn weight(&self) -> f32;
+    fn mass(&self) -> f32;
+    fn linear_drag(&self) -> f32;
+    fn gravity_scale(&self) -> f32;
+    fn collider(&self) -> Collider;
+    fn collision_mode(&self) -> CollisionMode;
 
     fn set_velocity(&mut self, velocity: Vector3<f32>);
     fn set_collisions(&mut self, collisions: Vector3<bool>);
-    fn set_weight(&mut self, weight: f32);
+    fn set_mass(&mut self, mass: f32);
+    fn set_linear_drag(&mut self, linear_drag: f32);
+    fn set_gravity_scale(&mut self, gravity_scale: f32);
+    fn set_collider(&mut self, collider: Collider);
+    fn set_collision_mode(&mut self, collision_mode: CollisionMode);
+
+    // Entities that share a mesh are drawn together, tinted by their color
+    fn mesh(&self) -> Option<MeshId>;
+
+    // Places the mesh in the world, which is read again on every tick
+    fn transform(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.center().to_vec())
+    }
 }
 
 #[derive(Clone)]
@@ -33,4 +57,9 @@ impl EntityHandle {
     pub fn borrow_mut(&mut self) -> RefMut<'_, dyn Entity> {
         self.0.deref().borrow_mut()
     }
+
+    // Whether both handles refer to the same entity
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,46 @@
+use cgmath::{
+    Point3,
+    Vector3
+};
+
+use super::entity::EntityHandle;
+
+// What an entity ran into
+#[derive(Clone)]
+pub enum CollisionTarget {
+    Tile(Point3<i16>),
+    Entity(EntityHandle),
+}
+
+#[derive(Clone)]
+pub struct CollisionEvent {
+    pub entity: EntityHandle,
+    pub target: CollisionTarget,
+    // Surface normal of the target at the point of contact, facing the entity
+    pub normal: Vector3<f32>,
+    // Speed at which the entity approached the target along the normal
+    pub speed: f32
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerPhase {
+    // The entity began overlapping the trigger during the last tick
+    Enter,
+    // The entity is still overlapping the trigger
+    Stay,
+    // The entity stopped overlapping the trigger, or was removed from the world
+    Exit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerSource<'a> {
+    Tile(Point3<i16>),
+    Volume(&'a str),
+}
+
+#[derive(Clone)]
+pub struct TriggerEvent<'a> {
+    pub entity: EntityHandle,
+    pub source: TriggerSource<'a>,
+    pub phase: TriggerPhase
+}
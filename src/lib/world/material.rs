@@ -0,0 +1,192 @@
+use crate::light::LightResponse;
+
+use super::{
+    World,
+    Drawable
+};
+
+// Describes how a surface responds to light
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Material {
+    // Exponent of the specular highlight, where higher values give smaller, sharper highlights
+    pub shininess: f32,
+    pub specular_strength: f32,
+    // Scales the ambient light from point lights and the sun
+    pub ambient: f32,
+    // Color of the light given off by the surface itself, which is added on top of any light that reaches it
+    // Drawables that emit light glow with its color when this is black
+    pub emissive: [f32; 3]
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self { shininess: 32.0, specular_strength: 1.0, ambient: 1.0, emissive: [0.0; 3] }
+    }
+}
+
+impl Material {
+    pub const METAL: Self = Self { shininess: 96.0, specular_strength: 1.5, ambient: 0.6, emissive: [0.0; 3] };
+    pub const STONE: Self = Self { shininess: 4.0, specular_strength: 0.05, ambient: 1.0, emissive: [0.0; 3] };
+    pub const PLASTIC: Self = Self { shininess: 24.0, specular_strength: 0.5, ambient: 1.0, emissive: [0.0; 3] };
+}
+
+// Layout of a material in the material buffer
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct MaterialUniform {
+    emissive: [f32; 3],
+    shininess: f32,
+    specular_strength: f32,
+    ambient: f32,
+    // Each material takes up a multiple of 16 bytes
+    _padding: [f32; 2]
+}
+
+impl MaterialUniform {
+    pub(crate) fn new(material: &Material) -> Self {
+        Self {
+            emissive: material.emissive,
+            shininess: material.shininess,
+            specular_strength: material.specular_strength,
+            ambient: material.ambient,
+            _padding: [0.0; 2]
+        }
+    }
+}
+
+// The color that a drawable glows with when it emits light and its material doesn't give off any of its own
+// It goes in each vertex or instance rather than the material, so that lights can change color without adding materials
+pub(crate) fn drawable_emissive(drawable: &(impl Drawable + ?Sized)) -> [u8; 4] {
+    match drawable.light() {
+        Some([r, g, b, _]) if drawable.material().emissive == [0.0; 3] => {
+            [r, g, b, 0.0].map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+        },
+        _ => [0; 4]
+    }
+}
+
+// Every material that tiles and entities are drawn with, which the shader looks up by index
+// Tiles keep their slot for as long as they use it, since their vertices only change when the tile does
+// Entity materials come after the tile slots and are gathered again every tick, so they never pile up
+pub(crate) struct MaterialTable {
+    // Each tile material with the number of tiles that use it, where unused slots are filled by the next new one
+    // The default material always comes first, and is never freed
+    tiles: Vec<(Material, usize)>,
+    entities: Vec<Material>,
+    changed: bool
+}
+
+impl Default for MaterialTable {
+    fn default() -> Self {
+        Self { tiles: vec![(Material::default(), 1)], entities: Vec::new(), changed: true }
+    }
+}
+
+impl MaterialTable {
+    // Finds the slot of a tile material and adds a user to it, taking a free slot if it is new
+    pub(crate) fn acquire(&mut self, material: Material) -> u32 {
+        let index = match self.tiles.iter().position(|(other, _)| same_material(other, &material)) {
+            Some(index) => index,
+            None => {
+                self.changed = true;
+                match self.tiles.iter().position(|(_, users)| *users == 0) {
+                    Some(index) => {
+                        self.tiles[index].0 = material;
+                        index
+                    },
+                    None => {
+                        self.tiles.push((material, 0));
+                        self.tiles.len() - 1
+                    }
+                }
+            }
+        };
+
+        self.tiles[index].1 += 1;
+        index as u32
+    }
+
+    // Removes a user from a tile material, which frees its slot once nothing uses it
+    pub(crate) fn release(&mut self, index: u32) {
+        if let Some((_, users)) = self.tiles.get_mut(index as usize) {
+            *users = users.saturating_sub(1);
+        }
+    }
+
+    // Frees every tile slot, for when all of the tiles are placed again
+    pub(crate) fn release_tiles(&mut self) {
+        for (_, users) in self.tiles.iter_mut().skip(1) {
+            *users = 0;
+        }
+    }
+
+    // The entity materials of this tick, where entities that look the same share an index
+    pub(crate) fn entity_indices(&mut self, materials: impl IntoIterator<Item = Material>) -> Vec<u32> {
+        let mut entities: Vec<Material> = Vec::new();
+        let indices = materials
+            .into_iter()
+            .map(|material| {
+                let index = entities.iter().position(|other| same_material(other, &material)).unwrap_or_else(|| {
+                    entities.push(material);
+                    entities.len() - 1
+                } );
+                (self.tiles.len() + index) as u32
+            } )
+            .collect();
+
+        let unchanged = entities.len() == self.entities.len()
+            && entities.iter().zip(&self.entities).all(|(a, b)| same_material(a, b));
+        if !unchanged {
+            self.entities = entities;
+            self.changed = true;
+        }
+
+        indices
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Material> {
+        self.tiles
+            .iter()
+            .map(|(material, _)| material)
+            .chain(&self.entities)
+    }
+
+    // Whether any material changed since this was last called
+    pub(crate) fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+}
+
+// Materials are compared by their bits, so that one with NaN in it still matches itself
+fn same_material(a: &Material, b: &Material) -> bool {
+    let bits = |material: &Material| [
+        material.shininess,
+        material.specular_strength,
+        material.ambient,
+        material.emissive[0],
+        material.emissive[1],
+        material.emissive[2]
+    ].map(f32::to_bits);
+
+    bits(a) == bits(b)
+}
+
+impl<'a> World<'a> {
+    // Every material used by a tile or entity, in the order that the shader looks them up,
+    // if any of them changed since this was last called
+    pub(crate) fn take_material_changes(&mut self) -> Option<Vec<MaterialUniform>> {
+        self.materials
+            .take_changed()
+            .then(|| self.materials.iter().map(MaterialUniform::new).collect())
+    }
+
+    // The largest ambient factor and specular strength of any material, including the default
+    pub(crate) fn light_response(&self) -> LightResponse {
+        self.materials
+            .iter()
+            .fold(LightResponse { ambient: 0.0, specular_strength: 0.0 }, |response, material| LightResponse {
+                ambient: response.ambient.max(material.ambient),
+                specular_strength: response.specular_strength.max(material.specular_strength)
+            } )
+    }
+}
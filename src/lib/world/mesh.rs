@@ -0,0 +1,134 @@
+use std::{
+    collections::BTreeMap,
+    ops::Range
+};
+
+use crate::vertex::{
+    Vertex,
+    Instance
+};
+
+use cgmath::{
+    Point3,
+    MetricSpace
+};
+
+use super::{
+    World,
+    Triangles,
+    transparency,
+    material
+};
+
+// Refers to a mesh that was added to a world
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MeshId(pub(crate) usize);
+
+// Where a mesh sits in the shared mesh buffers
+#[derive(Clone, Debug)]
+pub(crate) struct MeshRange {
+    pub(crate) indices: Range<u32>,
+    pub(crate) base_vertex: i32
+}
+
+// Every instance drawn on a tick, split into draw calls
+pub(crate) struct InstanceBatches {
+    pub(crate) instances: Vec<Instance>,
+    // Opaque instances that share a mesh
+    pub(crate) opaque: Vec<(MeshId, Range<u32>)>,
+    // Single instances that can be seen through, from back to front, with their squared distance from the eye
+    pub(crate) transparent: Vec<(f32, MeshId, u32)>
+}
+
+impl<'a> World<'a> {
+    // The mesh is in local space, and is uploaded to the GPU once
+    // Meshes can't be removed, so they should be shared between entities that look the same
+    pub fn add_mesh(&mut self, mut triangles: Triangles) -> MeshId {
+        let start = self.mesh_indices.len() as u32;
+
+        self.mesh_ranges.push(MeshRange {
+            indices: start..start + triangles.indices.len() as u32,
+            base_vertex: self.mesh_vertices.len() as i32
+        } );
+
+        // Meshes are drawn with the material and emissive color of each entity that uses them
+        for vertex in triangles.vertices.iter_mut() {
+            vertex.material = 0;
+            vertex.emissive = [0; 4];
+        }
+
+        self.mesh_vertices.append(&mut triangles.vertices);
+        self.mesh_indices.append(&mut triangles.indices);
+
+        MeshId(self.mesh_ranges.len() - 1)
+    }
+
+    pub fn mesh_count(&self) -> usize {
+        self.mesh_ranges.len()
+    }
+
+    // Every mesh is packed into a single list of vertices and indices, which only ever grows
+    pub(crate) fn mesh_geometry(&self) -> (&[Vertex], &[u32], &[MeshRange]) {
+        (&self.mesh_vertices, &self.mesh_indices, &self.mesh_ranges)
+    }
+
+    // Opaque instances are grouped by mesh, so that each mesh is drawn with a single call
+    // Instances that can be seen through come after them, one at a time from the furthest from the eye to the nearest
+    // The first instance is left untransformed, for geometry that is already in world space
+    pub(crate) fn build_instances(&mut self, eye: Point3<f32>) -> InstanceBatches {
+        let drawn = self.entity_objects
+            .iter()
+            .map(|e| e.borrow())
+            .filter_map(|entity| {
+                let mesh = entity.mesh().filter(|mesh| mesh.0 < self.mesh_ranges.len())?;
+                let [r, g, b] = entity.color();
+                Some((
+                    mesh,
+                    entity.transform(),
+                    [r, g, b, entity.alpha()],
+                    entity.center(),
+                    entity.material(),
+                    material::drawable_emissive(&*entity)
+                ))
+            } )
+            .collect::<Vec<_>>();
+
+        let materials = self.materials.entity_indices(drawn.iter().map(|(.., material, _)| *material));
+
+        let mut batches: BTreeMap<MeshId, Vec<Instance>> = BTreeMap::new();
+        let mut transparent = Vec::new();
+        for ((mesh, transform, color, center, _, emissive), material) in drawn.into_iter().zip(materials) {
+            let instance = Instance::new(
+                transform,
+                color,
+                self.sample_voxel_light(center),
+                material,
+                emissive
+            );
+
+            if transparency::is_transparent(color) {
+                transparent.push((center.distance2(eye), mesh, instance));
+            } else {
+                batches.entry(mesh).or_default().push(instance);
+            }
+        }
+
+        let mut instances = vec![Instance::default()];
+        let mut ranges = Vec::new();
+        for (mesh, mut batch) in batches {
+            let start = instances.len() as u32;
+            instances.append(&mut batch);
+            ranges.push((mesh, start..instances.len() as u32));
+        }
+
+        transparent.sort_by(|(a, ..), (b, ..)| b.total_cmp(a));
+
+        let mut transparent_instances = Vec::with_capacity(transparent.len());
+        for (distance, mesh, instance) in transparent {
+            transparent_instances.push((distance, mesh, instances.len() as u32));
+            instances.push(instance);
+        }
+
+        InstanceBatches { instances, opaque: ranges, transparent: transparent_instances }
+    }
+}
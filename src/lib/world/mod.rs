@@ -2,17 +2,68 @@ pub(crate) mod drawable;
 pub use drawable::{ Drawable, Triangles };
 
 pub(crate) mod tile;
-pub use tile::Tile;
+pub use tile::{ 
+    Tile, 
+    TileShape, 
+    Facing, 
+    PhysicalMaterial 
+};
+
+pub(crate) mod mesh;
+pub use mesh::MeshId;
 
 pub(crate) mod entity;
 pub use entity::{ Entity, EntityHandle };
 
+pub(crate) mod collider;
+pub use collider::{ Collider, CollisionMode, Contact };
+
+pub(crate) mod event;
+pub use event::{ 
+    CollisionEvent, 
+    CollisionTarget, 
+    TriggerEvent, 
+    TriggerPhase, 
+    TriggerSource 
+};
+
+mod physics;
+pub use physics::PhysicsConfig;
+
+mod trigger;
+pub use trigger::TriggerVolume;
+
+mod raycast;
+pub use raycast::RaycastHit;
+
+mod sun;
+pub use sun::Sun;
+
+mod voxel_light;
+pub use voxel_light::{ VoxelLighting, MAX_LIGHT_LEVEL };
+
+mod ambient_occlusion;
+
+mod texture_atlas;
+
+pub(crate) mod material;
+pub use material::Material;
+
+pub(crate) mod transparency;
+
+mod particle;
+pub use particle::{ ParticleConfig, ParticleEmitter };
+
+mod character;
+pub use character::{ CharacterController, CharacterConfig };
+
 use crate::{
-    vertex::Vertex, 
+    vertex::{ Vertex, ParticleVertex }, 
+    texture::TextureAtlas,
     light
 };
 
-use std::{collections::HashMap, time, cmp};
+use std::{collections::{HashMap, VecDeque}, ops::Range, time, cmp};
 
 use cgmath::{ 
     Point3, 
@@ -20,35 +71,244 @@ use cgmath::{
     Zero 
 };
 
-use wgpu::{
-    Buffer, 
-    Device, 
-    util::DeviceExt
-};
-
 #[derive(Default)]
 pub struct World<'a> {
     tile_objects: HashMap<Point3<i16>, Box<dyn Tile>>,
     tile_vertices: Vec<Vertex>,
     tile_indices: Vec<u32>,
+    // Indices into the tile vertices of triangles that can be seen through, which aren't in `tile_indices`
+    transparent_indices: Vec<u32>,
+    tile_ranges: HashMap<Point3<i16>, TileRange>,
+    // Tile vertices that belonged to removed geometry, which are reclaimed once they make up half of the vertices
+    unused_tile_vertices: usize,
+    // The most recent tile changes, of which `tile_revision` is the total number
+    tile_changes: VecDeque<Point3<i16>>,
+    tile_revision: usize,
+    tile_geometry_changes: Option<(usize, usize)>,
+    mesh_vertices: Vec<Vertex>,
+    mesh_indices: Vec<u32>,
+    mesh_ranges: Vec<mesh::MeshRange>,
     entity_objects: Vec<EntityHandle>,
     entity_tags: HashMap<&'a str, EntityHandle>,
-    entity_lifetimes: Vec<(time::Instant, time::Duration)>
+    entity_lifetimes: Vec<(time::Instant, time::Duration)>,
+    entity_forces: Vec<Vector3<f32>>,
+    entity_collisions: bool,
+    physics_config: PhysicsConfig,
+    entity_overlaps: Vec<(EntityHandle, EntityHandle)>,
+    collision_events: Vec<CollisionEvent>,
+    trigger_volumes: HashMap<&'a str, TriggerVolume>,
+    trigger_overlaps: Vec<(EntityHandle, TriggerSource<'a>)>,
+    trigger_events: Vec<TriggerEvent<'a>>,
+    particle_emitters: Vec<(Option<&'a str>, ParticleEmitter)>,
+    sun: Option<Sun>,
+    point_shadows: usize,
+    voxel_lighting: Option<VoxelLighting>,
+    light_grid: voxel_light::LightGrid,
+    ambient_occlusion: bool,
+    occlusion_revision: usize,
+    texture_atlas: Option<TextureAtlas>,
+    texture_atlas_changed: bool,
+    materials: material::MaterialTable,
+    debug_colliders: bool
+}
+
+// Where the geometry of a tile is kept in the tile vertices and indices
+// Geometry that is replaced by a smaller one keeps its whole range, with degenerate triangles padding the indices
+#[derive(Clone, Debug)]
+struct TileRange {
+    vertices: Range<usize>,
+    indices: Range<usize>,
+    transparent_indices: Range<usize>,
+    // Slot of the tile's material, which is freed along with its geometry
+    material: u32
+}
+
+// Color of the collider outlines drawn in debug mode
+const DEBUG_COLLIDER_COLOR: [f32; 3] = [0.2, 1.0, 0.2];
+
+// Number of tile changes that are remembered, older ones are forgotten
+const TILE_CHANGE_HISTORY: usize = 4096;
+
+// Copies a tile's indices into its range, offset to where its vertices are
+// The rest of the range is filled with degenerate triangles at the first vertex
+fn fill_tile_indices(range: &mut [u32], indices: &[u32], offset: u32) {
+    let (used, unused) = range.split_at_mut(indices.len());
+
+    for (target, index) in used.iter_mut().zip(indices) {
+        *target = *index + offset;
+    }
+    unused.fill(offset);
 }
 
 impl<'a> World<'a> {
+    // Replaces any tile that is already at the same position
     pub fn add_tile(&mut self, tile: impl Tile + 'static) {
-        let mut triangles = tile.build_object_data();
+        let position = tile.position();
+        let triangles = tile.build_object_data();
+        let material = self.materials.acquire(tile.material());
+        let emissive = material::drawable_emissive(&tile);
+
+        self.record_tile_change(position);
+        self.tile_objects.insert(position, Box::new(tile));
+        self.place_tile_geometry(position, triangles, material, emissive);
+        self.reclaim_tile_geometry();
+    }
+
+    pub fn remove_tile(&mut self, position: Point3<i16>) -> Option<Box<dyn Tile>> {
+        let tile = self.tile_objects.remove(&position)?;
+
+        self.record_tile_change(position);
+        self.clear_tile_geometry(position);
+        self.reclaim_tile_geometry();
+
+        Some(tile)
+    }
+
+    fn record_tile_change(&mut self, position: Point3<i16>) {
+        if self.tile_changes.len() == TILE_CHANGE_HISTORY {
+            self.tile_changes.pop_front();
+        }
+
+        self.tile_changes.push_back(position);
+        self.tile_revision += 1;
+    }
+
+    // Writes a tile's geometry over its old geometry if it fits there, or at the end otherwise
+    fn place_tile_geometry(&mut self, position: Point3<i16>, mut triangles: Triangles, material: u32, emissive: [u8; 4]) {
+        for vertex in triangles.vertices.iter_mut() {
+            vertex.material = material;
+            vertex.emissive = emissive;
+        }
+
+        // Only opaque faces are occluded, since faces that can be seen through don't hide their corners
+        let (mut opaque_indices, transparent_indices) = transparency::split_transparent(&triangles);
+        self.occlude_vertices(&mut triangles.vertices, &mut opaque_indices);
+        self.light_vertices(&mut triangles.vertices);
+
+        let fits = self.tile_ranges.get(&position).is_some_and(|range| {
+            triangles.vertices.len() <= range.vertices.len()
+                && opaque_indices.len() <= range.indices.len()
+                && transparent_indices.len() <= range.transparent_indices.len()
+        } );
+
+        if !fits {
+            self.clear_tile_geometry(position);
+            self.append_tile_geometry(position, triangles.vertices, &opaque_indices, &transparent_indices, material);
+            return;
+        }
+
+        let range = self.tile_ranges[&position].clone();
+        let offset = range.vertices.start as u32;
+        self.materials.release(range.material);
+        self.tile_ranges.get_mut(&position).unwrap().material = material;
+        self.mark_tile_geometry_changed(range.vertices.start, range.indices.start);
+
+        self.tile_vertices[range.vertices.start..][..triangles.vertices.len()].copy_from_slice(&triangles.vertices);
+        fill_tile_indices(&mut self.tile_indices[range.indices], &opaque_indices, offset);
+        fill_tile_indices(&mut self.transparent_indices[range.transparent_indices], &transparent_indices, offset);
+    }
+
+    fn append_tile_geometry(
+        &mut self,
+        position: Point3<i16>,
+        mut vertices: Vec<Vertex>,
+        opaque_indices: &[u32],
+        transparent_indices: &[u32],
+        material: u32
+    ) {
+        self.mark_tile_geometry_changed(self.tile_vertices.len(), self.tile_indices.len());
+
+        let range = TileRange {
+            vertices: self.tile_vertices.len()..self.tile_vertices.len() + vertices.len(),
+            indices: self.tile_indices.len()..self.tile_indices.len() + opaque_indices.len(),
+            transparent_indices: self.transparent_indices.len()..self.transparent_indices.len() + transparent_indices.len(),
+            material
+        };
+
+        let offset = range.vertices.start as u32;
+        self.tile_indices.extend(opaque_indices.iter().map(|i| *i + offset));
+        self.transparent_indices.extend(transparent_indices.iter().map(|i| *i + offset));
+        self.tile_vertices.append(&mut vertices);
+        self.tile_ranges.insert(position, range);
+    }
+
+    // Turns the triangles of a tile's geometry into degenerate ones, which draw nothing
+    fn clear_tile_geometry(&mut self, position: Point3<i16>) {
+        let range = match self.tile_ranges.remove(&position) {
+            Some(range) => range,
+            None => return
+        };
+
+        let offset = range.vertices.start as u32;
+        self.mark_tile_geometry_changed(self.tile_vertices.len(), range.indices.start);
+
+        fill_tile_indices(&mut self.tile_indices[range.indices], &[], offset);
+        fill_tile_indices(&mut self.transparent_indices[range.transparent_indices], &[], offset);
+
+        self.unused_tile_vertices += range.vertices.len();
+        self.materials.release(range.material);
+    }
+
+    // Rebuilds every tile once enough of the tile geometry was left behind by removed tiles
+    fn reclaim_tile_geometry(&mut self) {
+        if self.unused_tile_vertices * 2 > self.tile_vertices.len() {
+            self.rebuild_tile_geometry();
+        }
+    }
 
-        self.tile_objects.insert(tile.position(), Box::new(tile));
+    fn rebuild_tile_geometry(&mut self) {
+        self.tile_vertices.clear();
+        self.tile_indices.clear();
+        self.transparent_indices.clear();
+        self.tile_ranges.clear();
+        self.unused_tile_vertices = 0;
+        self.materials.release_tiles();
+        self.mark_tile_geometry_changed(0, 0);
 
-        let mut offset_indices = triangles.indices
+        let meshes = self.tile_objects
             .iter()
-            .map(|i| *i + self.tile_vertices.len() as u32)
-            .collect::<Vec<u32>>();
+            .map(|(position, tile)| (*position, tile.build_object_data(), tile.material(), material::drawable_emissive(tile.as_ref())))
+            .collect::<Vec<_>>();
+
+        for (position, triangles, material, emissive) in meshes {
+            let material = self.materials.acquire(material);
+            self.place_tile_geometry(position, triangles, material, emissive);
+        }
+    }
+
+    fn mark_tile_geometry_changed(&mut self, first_vertex: usize, first_index: usize) {
+        self.tile_geometry_changes = Some(match self.tile_geometry_changes {
+            Some((vertex, index)) => (vertex.min(first_vertex), index.min(first_index)),
+            None => (first_vertex, first_index)
+        } );
+    }
+
+    pub(crate) fn tile_geometry(&self) -> (&[Vertex], &[u32]) {
+        (&self.tile_vertices, &self.tile_indices)
+    }
+
+    // The first vertex and index of the tile geometry that changed since this was last called, if any did
+    pub(crate) fn take_tile_geometry_changes(&mut self) -> Option<(usize, usize)> {
+        self.tile_geometry_changes.take()
+    }
+
+    // Increases every time a tile is added or removed
+    pub fn tile_revision(&self) -> usize {
+        self.tile_revision
+    }
+
+    // Positions of the tiles that were added or removed since the given revision
+    // Only recent changes are remembered, so this is None when some of them were forgotten,
+    // and whatever depends on them has to be rebuilt from scratch
+    pub fn tile_changes_since(&self, revision: usize) -> Option<impl Iterator<Item = Point3<i16>> + '_> {
+        let missed = self.tile_revision.checked_sub(revision)?;
+        let forgotten = self.tile_changes.len().checked_sub(missed)?;
+
+        Some(self.tile_changes.iter().skip(forgotten).copied())
+    }
 
-        self.tile_indices.append(&mut offset_indices);
-        self.tile_vertices.append(&mut triangles.vertices);
+    pub fn tile_positions(&self) -> impl Iterator<Item = Point3<i16>> + '_ {
+        self.tile_objects.keys().copied()
     }
 
     pub fn add_entity(
@@ -59,6 +319,7 @@ impl<'a> World<'a> {
         let handle = EntityHandle::new(entity);
         let handle_clone = handle.clone();
         self.entity_objects.push(handle);
+        self.entity_forces.push(Vector3::zero());
         
         self.entity_lifetimes.push((
             time::Instant::now(), 
@@ -84,6 +345,20 @@ impl<'a> World<'a> {
         handle_clone
     }
 
+    pub fn remove_entity(&mut self, handle: &EntityHandle) -> bool {
+        match self.entity_objects.iter().position(|e| e.ptr_eq(handle)) {
+            Some(index) => { self.remove_entity_at(index); true },
+            None => false
+        }
+    }
+
+    fn remove_entity_at(&mut self, index: usize) {
+        let handle = self.entity_objects.remove(index);
+        self.entity_lifetimes.remove(index);
+        self.entity_forces.remove(index);
+        self.entity_tags.retain(|_, tagged| !tagged.ptr_eq(&handle));
+    }
+
     pub fn contains_tile(&self, position: &Point3<i16>) -> bool {
         self.tile_objects.contains_key(position)
     }
@@ -92,6 +367,17 @@ impl<'a> World<'a> {
         self.entity_tags.contains_key(tag)
     }
 
+    pub fn contains_entity_handle(&self, handle: &EntityHandle) -> bool {
+        self.entity_objects.iter().any(|e| e.ptr_eq(handle))
+    }
+
+    // Whether the tile at the given position blocks entities
+    pub fn is_solid(&self, position: Point3<i16>) -> bool {
+        self.tile_objects
+            .get(&position)
+            .is_some_and(|t| t.collision_mode() == CollisionMode::Solid)
+    }
+
     pub fn get_tile(&self, position: Point3<i16>) -> Option<&(dyn Tile + 'static)> {
         self.tile_objects
             .get(&position)
@@ -106,156 +392,127 @@ impl<'a> World<'a> {
         for index in (0..self.entity_lifetimes.len()).rev() {
             let (creation_instant, lifetime) = self.entity_lifetimes[index];
             if matches!(creation_instant.elapsed().cmp(&lifetime), cmp::Ordering::Greater | cmp::Ordering::Equal) {
-                self.entity_objects.remove(index);
-                self.entity_lifetimes.remove(index);
+                self.remove_entity_at(index);
             }
         }
     }
 
-    pub(crate) fn resolve_entity_physics(&mut self) {
-        for index in 0..self.entity_objects.len() {
-            let (velocity, weight) = {
-                let entity = self.entity_objects[index].borrow(); // TODO
-                
-                (entity.velocity(), entity.weight())
-            };
-
-            self.apply_displacement_to_entity(index, velocity);
-
-            let gravity = Vector3::new(0.0, -1.0 * weight, 0.0);
-            self.apply_displacement_to_entity(index, gravity);
-        }
+    pub fn entities_in_box(&self, min: Point3<f32>, max: Point3<f32>) -> Vec<EntityHandle> {
+        self.entity_objects
+            .iter()
+            .filter(|handle| {
+                let entity = handle.borrow();
+                entity.collider().intersects_box(entity.center(), min, max)
+            } )
+            .cloned()
+            .collect()
     }
 
-    fn apply_displacement_to_entity(
-        &mut self, 
-        entity_index: usize,
-        displacement: Vector3<f32>
-    ) {
-        let mut handler = self.entity_objects[entity_index].clone();
-        
-        let (center, weight, velocity) = {
-            let entity = handler.borrow();
-            (entity.center(), entity.weight(), entity.velocity())
-        };
+    pub fn entities_in_radius(&self, point: Point3<f32>, radius: f32) -> Vec<EntityHandle> {
+        self.entity_objects
+            .iter()
+            .filter(|handle| {
+                let entity = handle.borrow();
+                entity.collider().distance_to_point(entity.center(), point) < radius
+            } )
+            .cloned()
+            .collect()
+    }
 
-        // collision detection fails when the entity travels more than 1 tile in a single tick
-        let mut actual_displacement = displacement;
-        [actual_displacement.x, actual_displacement.y, actual_displacement.z]
-            .iter_mut()
-            .for_each(|c| *c = c.clamp(-1.0, 1.0));
+    pub fn entity_collisions(&self) -> bool {
+        self.entity_collisions
+    }
 
-        let increment = actual_displacement * 0.1;
+    pub fn set_entity_collisions(&mut self, enabled: bool) {
+        self.entity_collisions = enabled;
+    }
 
-        // Find the discrete coordinates of the tile containing the entity's new position (velocity + position)
-        fn get_discrete_point(pt: Point3<f32>) -> Point3<i16> {
-            (pt.x.round() as i16, pt.y.round() as i16, pt.z.round() as i16).into()
-        }
+    // Pairs of entities that overlapped during the last tick, including triggers
+    pub fn entity_overlaps(&self) -> &[(EntityHandle, EntityHandle)] {
+        &self.entity_overlaps
+    }
 
-        let mut initial_collisions: Option<Vector3<bool>> = None;
-        let mut collisions: Vector3<bool> = (false, false, false).into();
-        while self.contains_tile(&get_discrete_point(center + actual_displacement)) && !actual_displacement.is_zero() {
-            collisions.x = self.contains_tile(&get_discrete_point(
-                center + Vector3::new(actual_displacement.x, 0.0, 0.0)));
+    // Collisions that began during the last tick
+    // Events that are not drained are discarded when the next tick begins
+    pub fn drain_collision_events(&mut self) -> Vec<CollisionEvent> {
+        std::mem::take(&mut self.collision_events)
+    }
 
-            collisions.y = self.contains_tile(&get_discrete_point(
-                center + Vector3::new(0.0, actual_displacement.y, 0.0)));
+    pub fn debug_colliders(&self) -> bool {
+        self.debug_colliders
+    }
 
-            collisions.z = self.contains_tile(&get_discrete_point(
-                center + Vector3::new(0.0, 0.0, actual_displacement.z)));
-            
-            if initial_collisions.is_none() { initial_collisions = Some(collisions); }
+    pub fn set_debug_colliders(&mut self, enabled: bool) {
+        self.debug_colliders = enabled;
+    }
 
-            actual_displacement -= increment;
-        }
+    pub fn point_shadow_count(&self) -> usize {
+        self.point_shadows
+    }
 
-        {
-            let mut entity = handler.borrow_mut();
-            
-            let mut diff = Vector3::new(0.0, 0.0, 0.0);
-            if let Some(collisions) = initial_collisions {
-                if collisions.x { diff.x = displacement.x; }
-                if collisions.y { diff.y = displacement.y; }
-                if collisions.z { diff.z = displacement.z; }
-            }
-            
-            entity.set_center(center + actual_displacement);
-            entity.set_velocity((velocity - diff) * (1.0 - weight));
-            
-        }
+    // Number of point lights that cast shadows, chosen each tick by their intensity and distance to the camera
+    // Each one draws the scene six more times, so this is zero by default and capped at a handful
+    pub fn set_point_shadow_count(&mut self, count: usize) {
+        self.point_shadows = count.min(crate::shadow::MAX_POINT_SHADOWS);
     }
 
-    pub(crate) fn build_light_sources(&self) -> (light::LightSources, u32) {
-        let mut light_sources = light::LightSources { 
-            light_uniforms: [
-                light::Light::default(); 
-                light::MAX_LIGHT_SOURCES
-            ]
-        };
+    // Every light in the world, which is not limited in number
+    pub(crate) fn build_light_sources(&self) -> Vec<light::Light> {
+        let mut light_sources = Vec::new();
+        let response = self.light_response();
 
-        let mut light_count = 0;
-        for (.., tile) in self.tile_objects.iter() {
+        // Emissive tiles light the world through the light grid instead, when there is one
+        for (.., tile) in self.tile_objects.iter().filter(|_| self.voxel_lighting.is_none()) {
             if let Some(light) = tile.light() {
-                light_sources.light_uniforms[light_count].color = light;
-                light_sources.light_uniforms[light_count].position = [
-                    tile.position().x as f32,
-                    tile.position().y as f32,
-                    tile.position().z as f32,
-                    1.0
-                ];
-
-                light_count += 1;
+                light_sources.push(light::Light::new(tile.position().cast::<f32>().unwrap(), light, response));
             }
         }
 
-        for entity in self.entity_objects.iter().map(|e| e.borrow()) { // TODO
+        for entity in self.entity_objects.iter().map(|e| e.borrow()) {
             if let Some(light) = entity.light() {
-                light_sources.light_uniforms[light_count].color = light;
-                light_sources.light_uniforms[light_count].position = [
-                    entity.center().x,
-                    entity.center().y,
-                    entity.center().z,
-                    1.0
-                ];
-
-                light_count += 1;
+                light_sources.push(light::Light::new(entity.center(), light, response));
             }
         }
 
-        (light_sources, light_count as u32)
+        for (_, emitter) in self.particle_emitters.iter() {
+            if let Some((position, light)) = emitter.light() {
+                light_sources.push(light::Light::new(position, light, response));
+            }
+        }
 
+        light_sources
     }
 
-    pub(crate) fn build_geometry_buffers(&self, device: &mut Device) -> (Buffer, Buffer, u32) {
-        let mut indices = self.tile_indices.clone();
-        let mut vertices = self.tile_vertices.clone();
+    // Collider outlines, which are rebuilt on every tick
+    pub(crate) fn build_debug_geometry(&self) -> Triangles {
+        let mut triangles = Triangles { vertices: Vec::new(), indices: Vec::new() };
+        if !self.debug_colliders {
+            return triangles;
+        }
 
-        for entity in self.entity_objects.iter().map(|e| e.borrow()) { // TODO
-            let mut triangles = entity.build_object_data();
-            let mut offset_indices = triangles.indices
+        for entity in self.entity_objects.iter().map(|e| e.borrow()) {
+            let mut outline = entity.collider().build_debug_data(entity.center(), DEBUG_COLLIDER_COLOR);
+            let mut offset_indices = outline.indices
                 .iter()
-                .map(|i| *i + vertices.len() as u32)
+                .map(|i| *i + triangles.vertices.len() as u32)
                 .collect::<Vec<u32>>();
-            indices.append(&mut offset_indices);
-            vertices.append(&mut triangles.vertices);
+
+            triangles.indices.append(&mut offset_indices);
+            triangles.vertices.append(&mut outline.vertices);
         }
 
-        let vertex_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(vertices.as_slice()),
-                usage: wgpu::BufferUsages::VERTEX
-            }
-        );
+        triangles
+    }
 
-        let index_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(indices.as_slice()),
-                usage: wgpu::BufferUsages::INDEX,
-            }
-        );
+    // All particles are drawn together, in a single batch
+    pub(crate) fn build_particle_geometry(&self) -> (Vec<ParticleVertex>, Vec<u32>) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for (_, emitter) in self.particle_emitters.iter() {
+            emitter.build_vertices(&mut vertices, &mut indices);
+        }
 
-        (vertex_buffer, index_buffer, indices.len() as u32)
+        (vertices, indices)
     }
 }
@@ -0,0 +1,290 @@
+use cgmath::{
+    Point3,
+    Vector3,
+    InnerSpace,
+    EuclideanSpace,
+    Zero
+};
+
+use crate::vertex::ParticleVertex;
+
+use super::{
+    World,
+    Collider,
+    EntityHandle
+};
+
+// How particles look and move, all in tiles and ticks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParticleConfig {
+    // Particles emitted on each tick, which can be fractional
+    pub rate: f32,
+    // Direction that particles are launched in
+    pub direction: Vector3<f32>,
+    // Largest angle between a particle's launch direction and `direction`, in radians
+    pub spread: f32,
+    pub speed: f32,
+    // Fraction of the speed that varies randomly between particles
+    pub speed_variance: f32,
+    // Multiplier on the world's gravity
+    pub gravity_scale: f32,
+    // Fraction of the velocity lost on each tick
+    pub drag: f32,
+    // Number of ticks that each particle lives for
+    pub lifetime: u32,
+    // Color at the start and end of a particle's life
+    pub start_color: [f32; 3],
+    pub end_color: [f32; 3],
+    // Width of a particle at the start and end of its life
+    pub start_size: f32,
+    pub end_size: f32,
+    // Particles bounce off solid tiles, keeping this fraction of their speed
+    pub collisions: Option<f32>,
+    // Light given off by the emitter while any of its particles are alive
+    // The light sits at the middle of the particles and fades as they die
+    pub light: Option<[f32; 4]>
+}
+
+impl Default for ParticleConfig {
+    fn default() -> Self {
+        Self {
+            rate: 1.0,
+            direction: Vector3::unit_y(),
+            spread: std::f32::consts::PI,
+            speed: 0.1,
+            speed_variance: 0.5,
+            gravity_scale: 1.0,
+            drag: 0.02,
+            lifetime: 30,
+            start_color: [1.0; 3],
+            end_color: [1.0; 3],
+            start_size: 0.1,
+            end_size: 0.0,
+            collisions: None,
+            light: None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Particle {
+    position: Point3<f32>,
+    velocity: Vector3<f32>,
+    age: u32
+}
+
+// Spawns particles and simulates them once they are alive
+pub struct ParticleEmitter {
+    pub position: Point3<f32>,
+    pub config: ParticleConfig,
+    // Ticks left to emit for, or `None` to emit until removed
+    pub duration: Option<u32>,
+    // Emitters attached to an entity follow it, and stop emitting when it is removed
+    attached: Option<EntityHandle>,
+    burst: usize,
+    particles: Vec<Particle>,
+    accumulated: f32,
+    seed: u32
+}
+
+impl ParticleEmitter {
+    pub fn new(position: Point3<f32>, config: ParticleConfig) -> Self {
+        Self {
+            position,
+            config,
+            duration: None,
+            attached: None,
+            burst: 0,
+            particles: Vec::new(),
+            accumulated: 0.0,
+            // Emitters in different places start from different random numbers
+            seed: (position.x.to_bits() ^ position.y.to_bits().rotate_left(11) ^ position.z.to_bits().rotate_left(22)).max(1)
+        }
+    }
+
+    pub fn with_duration(mut self, ticks: u32) -> Self {
+        self.duration = Some(ticks);
+        self
+    }
+
+    // Particles that are emitted all at once on the first tick
+    pub fn with_burst(mut self, count: usize) -> Self {
+        self.burst = count;
+        self
+    }
+
+    pub fn attached_to(mut self, handle: &EntityHandle) -> Self {
+        self.attached = Some(handle.clone());
+        self
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    // Whether the emitter will emit any more particles
+    pub fn is_emitting(&self) -> bool {
+        self.burst > 0 || self.duration.is_none_or(|duration| duration > 0)
+    }
+
+    // Xorshift, between -1 and 1
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+
+        (self.seed as f32 / u32::MAX as f32) * 2.0 - 1.0
+    }
+
+    fn spawn(&mut self) {
+        let direction = if self.config.direction.is_zero() {
+            Vector3::unit_y()
+        } else {
+            self.config.direction.normalize()
+        };
+
+        // Pick a direction within the cone around the launch direction
+        let tangent = if direction.x.abs() < 0.9 { Vector3::unit_x() } else { Vector3::unit_z() };
+        let tangent = direction.cross(tangent).normalize();
+        let bitangent = direction.cross(tangent);
+
+        let angle = (self.random() * 0.5 + 0.5) * self.config.spread;
+        let turn = self.random() * std::f32::consts::PI;
+        let launch = direction * angle.cos() +
+            (tangent * turn.cos() + bitangent * turn.sin()) * angle.sin();
+
+        let speed = self.config.speed * (1.0 + self.random() * self.config.speed_variance);
+
+        self.particles.push(Particle {
+            position: self.position,
+            velocity: launch * speed,
+            age: 0
+        } );
+    }
+
+    // Where the light given off by the emitter sits, and how bright it is
+    pub(crate) fn light(&self) -> Option<(Point3<f32>, [f32; 4])> {
+        let light = self.config.light?;
+        if self.particles.is_empty() { return None; }
+
+        let positions = self.particles.iter().map(|p| p.position).collect::<Vec<_>>();
+        let life = self.particles
+            .iter()
+            .map(|p| 1.0 - p.age as f32 / self.config.lifetime.max(1) as f32)
+            .fold(0.0, f32::max);
+
+        Some((Point3::centroid(&positions), [light[0], light[1], light[2], light[3] * life]))
+    }
+
+    pub(crate) fn build_vertices(&self, vertices: &mut Vec<ParticleVertex>, indices: &mut Vec<u32>) {
+        let lerp = |start: f32, end: f32, t: f32| start + (end - start) * t;
+
+        for particle in self.particles.iter() {
+            let t = particle.age as f32 / self.config.lifetime.max(1) as f32;
+            let size = lerp(self.config.start_size, self.config.end_size, t);
+            let color = [
+                lerp(self.config.start_color[0], self.config.end_color[0], t),
+                lerp(self.config.start_color[1], self.config.end_color[1], t),
+                lerp(self.config.start_color[2], self.config.end_color[2], t)
+            ];
+
+            let offset = vertices.len() as u32;
+            for corner in [[-0.5, -0.5], [0.5, -0.5], [0.5, 0.5], [-0.5, 0.5]] {
+                vertices.push(ParticleVertex {
+                    center: particle.position.into(),
+                    corner,
+                    size,
+                    color
+                } );
+            }
+
+            indices.extend_from_slice(&[
+                offset, offset + 1, offset + 2,
+                offset, offset + 2, offset + 3
+            ]);
+        }
+    }
+}
+
+impl<'a> World<'a> {
+    // The emitter is removed once it has finished emitting and all of its particles have died
+    pub fn add_emitter(&mut self, emitter: ParticleEmitter) {
+        self.particle_emitters.push((None, emitter));
+    }
+
+    pub fn add_emitter_with_tag(&mut self, tag: &'a str, emitter: ParticleEmitter) {
+        self.particle_emitters.push((Some(tag), emitter));
+    }
+
+    pub fn get_emitter_mut(&mut self, tag: &str) -> Option<&mut ParticleEmitter> {
+        self.particle_emitters
+            .iter_mut()
+            .find(|(emitter_tag, _)| *emitter_tag == Some(tag))
+            .map(|(_, emitter)| emitter)
+    }
+
+    // Particles that are still alive disappear along with the emitter
+    pub fn remove_emitter(&mut self, tag: &str) -> Option<ParticleEmitter> {
+        let index = self.particle_emitters
+            .iter()
+            .position(|(emitter_tag, _)| *emitter_tag == Some(tag))?;
+
+        Some(self.particle_emitters.remove(index).1)
+    }
+
+    pub(crate) fn resolve_particles(&mut self) {
+        let gravity = self.physics_config.gravity;
+        let mut emitters = std::mem::take(&mut self.particle_emitters);
+
+        for (_, emitter) in emitters.iter_mut() {
+            // Attached emitters stop emitting when their entity is removed
+            if let Some(handle) = emitter.attached.as_ref() {
+                if self.contains_entity_handle(handle) {
+                    emitter.position = handle.borrow().center();
+                } else {
+                    emitter.attached = None;
+                    emitter.duration = Some(0);
+                }
+            }
+
+            if emitter.duration.is_none_or(|duration| duration > 0) {
+                emitter.accumulated += emitter.config.rate;
+                emitter.duration = emitter.duration.map(|duration| duration - 1);
+            }
+
+            let count = emitter.burst + emitter.accumulated as usize;
+            emitter.accumulated = emitter.accumulated.fract();
+            emitter.burst = 0;
+
+            for _ in 0..count {
+                emitter.spawn();
+            }
+
+            let config = emitter.config;
+            emitter.particles.retain_mut(|particle| {
+                particle.age += 1;
+                particle.velocity = (particle.velocity + gravity * config.gravity_scale) * (1.0 - config.drag);
+
+                match config.collisions {
+                    // Particles are points, moved one axis at a time so that they can bounce off each face
+                    Some(restitution) => for axis in 0..3 {
+                        let mut next = particle.position;
+                        next[axis] += particle.velocity[axis];
+
+                        if self.collider_intersects_tiles(&Collider::default(), next) {
+                            particle.velocity[axis] *= -restitution;
+                        } else {
+                            particle.position = next;
+                        }
+                    },
+                    None => particle.position += particle.velocity
+                }
+
+                particle.age < config.lifetime
+            } );
+        }
+
+        emitters.retain(|(tag, emitter)| tag.is_some() || emitter.is_emitting() || !emitter.particles.is_empty());
+        self.particle_emitters = emitters;
+    }
+}
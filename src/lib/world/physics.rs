@@ -0,0 +1,597 @@
+use cgmath::{
+    Point3,
+    Vector3,
+    InnerSpace,
+    EuclideanSpace,
+    Zero
+};
+
+use super::{
+    World,
+    Tile,
+    TileShape,
+    Collider,
+    CollisionMode,
+    CollisionEvent,
+    CollisionTarget,
+    EntityHandle
+};
+
+// Largest distance a collider moves between collision checks
+const SWEEP_STEP: f32 = 0.25;
+
+// Number of bisections used to find the point of contact
+const SWEEP_REFINEMENTS: usize = 8;
+
+// Extra height an entity may be lifted by when walking up a ramp
+const RAMP_CLIMB_MARGIN: f32 = 0.05;
+
+// How far beneath a collider to look for the ground
+const GROUND_PROBE_DISTANCE: f32 = 0.02;
+
+// Impacts slower than this never bounce
+const BOUNCE_THRESHOLD: f32 = 0.05;
+
+// Fraction of the approach speed kept after two entities collide
+const ENTITY_RESTITUTION: f32 = 0.3;
+
+// World-wide physics settings, which can be changed between ticks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsConfig {
+    // Acceleration applied to every entity, in tiles per tick squared
+    // Characters treat the opposite direction as up, but ramps and one-way platforms always face +y
+    pub gravity: Vector3<f32>,
+    // Largest speed an entity can reach by falling, in tiles per tick
+    pub terminal_velocity: f32,
+    // Fraction of every entity's velocity that is lost on each tick, on top of its own drag
+    pub damping: f32,
+    // Number of smaller steps that each tick is divided into
+    // More substeps make collisions between fast entities more reliable
+    pub substeps: usize
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            gravity: Vector3::new(0.0, -0.03, 0.0),
+            terminal_velocity: 1.0,
+            damping: 0.0,
+            substeps: 1
+        }
+    }
+}
+
+// Whether the collider overlaps the tile's collision shape
+fn tile_intersects_collider(tile: &dyn Tile, collider: &Collider, center: Point3<f32>) -> bool {
+    let tile_center = tile.position().cast::<f32>().unwrap();
+    let intersects_box = |&(min, max): &(Point3<f32>, Point3<f32>)| {
+        collider.intersects_box(center, tile_center + min.to_vec(), tile_center + max.to_vec())
+    };
+
+    match tile.collision_shape() {
+        TileShape::Ramp { facing } => {
+            TileShape::Full.boxes().iter().any(intersects_box) && 
+                collider.penetrates_plane(center, tile_center, TileShape::ramp_normal(facing))
+        },
+        shape => shape.boxes().iter().any(intersects_box)
+    }
+}
+
+// The axis that is closest to `up`, which collisions on the ground are reported along
+pub(crate) fn vertical_axis(up: Vector3<f32>) -> usize {
+    (0..3)
+        .max_by(|a, b| up[*a].abs().total_cmp(&up[*b].abs()))
+        .unwrap_or(1)
+}
+
+// Converts a fraction lost per tick into the fraction lost over part of a tick
+fn fraction_per_step(fraction: f32, step: f32) -> f32 {
+    1.0 - (1.0 - fraction.clamp(0.0, 1.0)).powf(step)
+}
+
+impl<'a> World<'a> {
+    pub fn physics_config(&self) -> PhysicsConfig {
+        self.physics_config
+    }
+
+    pub fn set_physics_config(&mut self, physics_config: PhysicsConfig) {
+        self.physics_config = physics_config;
+    }
+
+    // Direction opposite to gravity, or +y when there is no gravity
+    pub fn up(&self) -> Vector3<f32> {
+        let gravity = self.physics_config.gravity;
+
+        if gravity.is_zero() {
+            Vector3::unit_y()
+        } else {
+            -gravity.normalize()
+        }
+    }
+
+    pub(crate) fn resolve_entity_physics(&mut self) {
+        self.collision_events.clear();
+
+        let substeps = self.physics_config.substeps.max(1);
+        let step = (substeps as f32).recip();
+
+        for _ in 0..substeps {
+            for index in 0..self.entity_objects.len() {
+                self.integrate_entity(index, step);
+            }
+
+            let previous_overlaps = std::mem::take(&mut self.entity_overlaps);
+            if self.entity_collisions {
+                self.resolve_entity_collisions(&previous_overlaps);
+            }
+        }
+
+        // Forces only last for a single tick
+        self.entity_forces
+            .iter_mut()
+            .for_each(|force| *force = Vector3::zero());
+    }
+
+    // Advances a single entity by `step` ticks
+    fn integrate_entity(&mut self, index: usize, step: f32) {
+        let config = self.physics_config;
+
+        let mut handle = self.entity_objects[index].clone();
+
+        let (center, velocity, collider, mass, linear_drag, gravity_scale, previous_collisions) = {
+            let entity = handle.borrow(); // TODO
+            
+            (
+                entity.center(), 
+                entity.velocity(), 
+                entity.collider(), 
+                entity.mass(), 
+                entity.linear_drag(), 
+                entity.gravity_scale(), 
+                entity.collisions()
+            )
+        };
+
+        // F = m·a, on top of gravity which accelerates every entity equally
+        let mut acceleration = config.gravity * gravity_scale;
+        if mass > 0.0 {
+            acceleration += self.entity_forces[index] / mass;
+        }
+
+        let mut velocity = (velocity + acceleration * step) 
+            * (1.0 - fraction_per_step(linear_drag, step))
+            * (1.0 - fraction_per_step(config.damping, step));
+
+        // Limit the speed at which entities fall
+        if !config.gravity.is_zero() {
+            let down = config.gravity.normalize();
+            let falling_speed = velocity.dot(down);
+            if falling_speed > config.terminal_velocity {
+                velocity -= down * (falling_speed - config.terminal_velocity);
+            }
+        }
+
+        let (position, blocked) = self.move_collider(&collider, center, velocity * step);
+
+        {
+            let mut entity = handle.borrow_mut();
+
+            entity.set_center(position);
+            entity.set_velocity(velocity);
+        }
+
+        // The tile struck along each axis
+        let mut hits: [Option<CollisionEvent>; 3] = [None, None, None];
+        for axis in 0..3 {
+            if let Some(tile) = blocked[axis] {
+                hits[axis] = Some(CollisionEvent {
+                    entity: handle.clone(),
+                    target: CollisionTarget::Tile(tile),
+                    normal: self.tile_contact_normal(tile, axis, velocity),
+                    speed: velocity[axis].abs()
+                } );
+            }
+        }
+
+        self.apply_tile_materials(&mut handle, &hits, step);
+
+        let collisions = Vector3::new(hits[0].is_some(), hits[1].is_some(), hits[2].is_some());
+        handle.borrow_mut().set_collisions(collisions);
+
+        // Entities resting against a tile only report the initial impact
+        for (axis, hit) in hits.into_iter().enumerate() {
+            if let Some(hit) = hit {
+                if !previous_collisions[axis] {
+                    self.collision_events.push(hit);
+                }
+            }
+        }
+    }
+
+    // The force is applied over the course of the next tick
+    pub fn apply_force(&mut self, handle: &EntityHandle, force: Vector3<f32>) {
+        if let Some(index) = self.entity_objects.iter().position(|e| e.ptr_eq(handle)) {
+            self.entity_forces[index] += force;
+        }
+    }
+
+    // Instantly changes the entity's momentum
+    pub fn apply_impulse(&mut self, handle: &EntityHandle, impulse: Vector3<f32>) {
+        let mut handle = handle.clone();
+        let mut entity = handle.borrow_mut();
+
+        let mass = entity.mass();
+        if mass > 0.0 {
+            let velocity = entity.velocity() + impulse / mass;
+            entity.set_velocity(velocity);
+        }
+    }
+
+    // Applies bounces, friction and conveyor belts from the tiles that the entity touched
+    fn apply_tile_materials(
+        &self, 
+        handle: &mut EntityHandle, 
+        hits: &[Option<CollisionEvent>; 3], 
+        step: f32
+    ) {
+        let mut velocity = handle.borrow().velocity();
+
+        for (axis, hit) in hits.iter().enumerate() {
+            let (hit, material) = match hit {
+                Some(hit @ CollisionEvent { target: CollisionTarget::Tile(tile), .. }) => {
+                    (hit, self.get_tile(*tile).map(|t| t.physical_material()).unwrap_or_default())
+                },
+                _ => continue
+            };
+
+            // Cancel the velocity into the surface, returning part of the impact as a bounce
+            // Slow impacts don't bounce, so that resting entities settle
+            if velocity[axis] * hit.normal[axis] <= 0.0 {
+                velocity[axis] = if hit.speed > BOUNCE_THRESHOLD {
+                    hit.normal[axis].signum() * hit.speed * material.restitution
+                } else {
+                    0.0
+                };
+            }
+
+            // Drag the sliding velocity towards the velocity of the surface
+            let surface = material.conveyor.unwrap_or_else(Vector3::zero);
+            let friction = fraction_per_step(material.friction, step);
+            for tangent in (0..3).filter(|tangent| *tangent != axis) {
+                velocity[tangent] = surface[tangent] + 
+                    (velocity[tangent] - surface[tangent]) * (1.0 - friction);
+            }
+        }
+
+        handle.borrow_mut().set_velocity(velocity);
+    }
+
+    // Moves a collider through the world, stopping at tiles
+    // Returns the new position and the tile that blocked the movement along each axis
+    fn move_collider(
+        &self,
+        collider: &Collider,
+        center: Point3<f32>,
+        displacement: Vector3<f32>
+    ) -> (Point3<f32>, [Option<Point3<i16>>; 3]) {
+        // Each axis is resolved separately so the entity can slide along walls
+        let mut position = center;
+        let mut blocked = [None; 3];
+        for axis in 0..3 {
+            let (travelled, tile) = self.sweep_collider(
+                collider, 
+                position, 
+                axis, 
+                displacement[axis]
+            );
+
+            position[axis] += travelled;
+            blocked[axis] = tile;
+
+            // Entities walking into a ramp are lifted onto its surface
+            if let Some(tile) = tile.filter(|_| axis != 1) {
+                let is_ramp = self.tile_objects
+                    .get(&tile)
+                    .is_some_and(|t| matches!(t.collision_shape(), TileShape::Ramp { .. }));
+
+                if is_ramp {
+                    let remaining = displacement[axis] - travelled;
+
+                    // A ramp never rises faster than the distance travelled along it
+                    let max_lift = remaining.abs() + RAMP_CLIMB_MARGIN;
+                    if let Some(lift) = self.find_climb_height(collider, position, axis, remaining, Vector3::unit_y(), max_lift) {
+                        position[axis] += remaining;
+                        position.y += lift;
+                        blocked[axis] = None;
+                    }
+                }
+            }
+        }
+
+        (position, blocked)
+    }
+
+    // Smallest lift along `up`, no higher than `max_lift`, that lets the collider complete a movement along `axis`
+    pub(crate) fn find_climb_height(
+        &self,
+        collider: &Collider,
+        position: Point3<f32>,
+        axis: usize,
+        distance: f32,
+        up: Vector3<f32>,
+        max_lift: f32
+    ) -> Option<f32> {
+        let probe = |lift: f32| {
+            let mut probe = position + up * lift;
+            probe[axis] += distance;
+            probe
+        };
+
+        // There must be headroom above the entity, and space at the end of the movement
+        let raised = position + up * max_lift;
+        if self.collider_intersects_tiles(collider, raised) || 
+            self.collider_intersects_tiles(collider, probe(max_lift)) {
+            return None;
+        }
+
+        let (mut free, mut blocked) = (max_lift, 0.0);
+        for _ in 0..SWEEP_REFINEMENTS {
+            let mid = (free + blocked) * 0.5;
+
+            if self.collider_intersects_tiles(collider, probe(mid)) {
+                blocked = mid;
+            } else {
+                free = mid;
+            }
+        }
+
+        Some(free)
+    }
+
+    // Moves a collider along a single axis until it runs into a tile
+    // Returns the distance travelled and the tile that blocked the movement
+    fn sweep_collider(
+        &self,
+        collider: &Collider,
+        start: Point3<f32>,
+        axis: usize,
+        distance: f32
+    ) -> (f32, Option<Point3<i16>>) {
+        if distance == 0.0 {
+            return (0.0, None);
+        }
+
+        // Entities that are already stuck inside tiles may only move away from their centers
+        // Those tiles are then ignored, so that the entity can leave them without getting caught again
+        let stuck = self.overlapping_tiles(collider, start, CollisionMode::Solid)
+            .filter(|position| self.tile_objects[position].collision_shape() != TileShape::OneWay)
+            .collect::<Vec<_>>();
+
+        let deeper = stuck.iter().find(|tile| (start[axis] - tile[axis] as f32) * distance < 0.0);
+        if let Some(tile) = deeper {
+            return (0.0, Some(*tile));
+        }
+
+        // One-way platforms only block entities that are falling onto them
+        let falling_from = if axis == 1 && distance < 0.0 { Some(start) } else { None };
+
+        let steps = (distance.abs() / SWEEP_STEP).ceil().max(1.0) as usize;
+        let step = distance / steps as f32;
+
+        let mut travelled = 0.0;
+        for _ in 0..steps {
+            let mut next = start;
+            next[axis] += travelled + step;
+
+            if let Some(tile) = self.find_intersecting_tile(collider, next, falling_from, &stuck) {
+                // Narrow down the point of contact within the blocked step
+                let (mut free, mut blocked) = (0.0, step);
+                for _ in 0..SWEEP_REFINEMENTS {
+                    let mid = (free + blocked) * 0.5;
+
+                    let mut probe = start;
+                    probe[axis] += travelled + mid;
+
+                    if self.find_intersecting_tile(collider, probe, falling_from, &stuck).is_some() {
+                        blocked = mid;
+                    } else {
+                        free = mid;
+                    }
+                }
+
+                return (travelled + free, Some(tile));
+            }
+
+            travelled += step;
+        }
+
+        (travelled, None)
+    }
+
+    // Normal of the surface directly beneath the collider, in the direction of gravity
+    // One-way platforms are included
+    pub fn ground_normal(&self, collider: &Collider, center: Point3<f32>) -> Option<Vector3<f32>> {
+        let up = self.up();
+        let probe = center - up * GROUND_PROBE_DISTANCE;
+
+        self.find_intersecting_tile(collider, probe, Some(center), &[])
+            .map(|tile| self.tile_contact_normal(tile, vertical_axis(up), -up))
+    }
+
+    // Only solid tiles are taken into account, one-way platforms are ignored
+    pub fn collider_intersects_tiles(&self, collider: &Collider, center: Point3<f32>) -> bool {
+        self.find_intersecting_tile(collider, center, None, &[]).is_some()
+    }
+
+    // One-way platforms are included if the collider is falling from a point above them
+    // Tiles in `ignored` never count
+    fn find_intersecting_tile(
+        &self, 
+        collider: &Collider, 
+        center: Point3<f32>,
+        falling_from: Option<Point3<f32>>,
+        ignored: &[Point3<i16>]
+    ) -> Option<Point3<i16>> {
+        self.overlapping_tiles(collider, center, CollisionMode::Solid).find(|position| {
+            if ignored.contains(position) {
+                return false;
+            }
+
+            let tile = &self.tile_objects[position];
+
+            match (tile.collision_shape(), falling_from) {
+                (TileShape::OneWay, Some(start)) => !tile_intersects_collider(tile.as_ref(), collider, start),
+                (TileShape::OneWay, None) => false,
+                _ => true
+            }
+        } )
+    }
+
+    // Positions of the tiles with the given collision mode whose shapes overlap the collider
+    pub(crate) fn overlapping_tiles<'b>(
+        &'b self, 
+        collider: &'b Collider, 
+        center: Point3<f32>,
+        collision_mode: CollisionMode
+    ) -> impl Iterator<Item = Point3<i16>> + 'b {
+        let (min, max) = collider.bounds(center);
+
+        let xs = (min.x.round() as i16)..=(max.x.round() as i16);
+        let ys = (min.y.round() as i16)..=(max.y.round() as i16);
+        let zs = (min.z.round() as i16)..=(max.z.round() as i16);
+
+        xs.flat_map(move |x| {
+            let zs = zs.clone();
+            ys.clone().flat_map(move |y| zs.clone().map(move |z| Point3::new(x, y, z)))
+        } ).filter(move |position| {
+            match self.tile_objects.get(position) {
+                Some(tile) if tile.collision_mode() == collision_mode => {
+                    tile_intersects_collider(tile.as_ref(), collider, center)
+                },
+                _ => false
+            }
+        } )
+    }
+
+    // Surface normal of the part of a tile that was struck while moving along `axis`
+    fn tile_contact_normal(&self, tile: Point3<i16>, axis: usize, velocity: Vector3<f32>) -> Vector3<f32> {
+        let mut normal = Vector3::new(0.0, 0.0, 0.0);
+        normal[axis] = -velocity[axis].signum();
+
+        // Entities landing on a ramp or walking into its low side touch the slope
+        if let Some(TileShape::Ramp { facing }) = self.tile_objects.get(&tile).map(|t| t.collision_shape()) {
+            let slope = TileShape::ramp_normal(facing);
+            if slope.dot(normal) > 0.0 {
+                return slope;
+            }
+        }
+
+        normal
+    }
+
+    // Separates overlapping solid entities and exchanges momentum between them
+    // Entities without mass cannot be pushed
+    fn resolve_entity_collisions(&mut self, previous_overlaps: &[(EntityHandle, EntityHandle)]) {
+        for first in 0..self.entity_objects.len() {
+            for second in (first + 1)..self.entity_objects.len() {
+                let mut first_handle = self.entity_objects[first].clone();
+                let mut second_handle = self.entity_objects[second].clone();
+
+                let (first_center, first_collider, first_mode, first_velocity, first_mass) = {
+                    let entity = first_handle.borrow();
+                    (entity.center(), entity.collider(), entity.collision_mode(), entity.velocity(), entity.mass())
+                };
+
+                let (second_center, second_collider, second_mode, second_velocity, second_mass) = {
+                    let entity = second_handle.borrow();
+                    (entity.center(), entity.collider(), entity.collision_mode(), entity.velocity(), entity.mass())
+                };
+
+                if first_mode == CollisionMode::Ghost || second_mode == CollisionMode::Ghost {
+                    continue;
+                }
+
+                let contact = match first_collider.contact(first_center, &second_collider, second_center) {
+                    Some(contact) => contact,
+                    None => continue
+                };
+
+                self.entity_overlaps.push((first_handle.clone(), second_handle.clone()));
+
+                // Pairs that were already touching during the last tick are not reported again
+                let is_new_contact = !previous_overlaps.iter().any(|(a, b)| {
+                    (a.ptr_eq(&first_handle) && b.ptr_eq(&second_handle)) ||
+                    (a.ptr_eq(&second_handle) && b.ptr_eq(&first_handle))
+                } );
+
+                if is_new_contact {
+                    let speed = (first_velocity - second_velocity).dot(contact.normal).max(0.0);
+
+                    self.collision_events.push(CollisionEvent {
+                        entity: first_handle.clone(),
+                        target: CollisionTarget::Entity(second_handle.clone()),
+                        normal: -contact.normal,
+                        speed
+                    } );
+
+                    self.collision_events.push(CollisionEvent {
+                        entity: second_handle.clone(),
+                        target: CollisionTarget::Entity(first_handle.clone()),
+                        normal: contact.normal,
+                        speed
+                    } );
+                }
+
+                if first_mode == CollisionMode::Trigger || second_mode == CollisionMode::Trigger {
+                    continue;
+                }
+
+                let inverse_mass = |mass: f32| if mass > 0.0 { mass.recip() } else { 0.0 };
+                let (first_inverse_mass, second_inverse_mass) = (
+                    inverse_mass(first_mass), 
+                    inverse_mass(second_mass)
+                );
+
+                let total_inverse_mass = first_inverse_mass + second_inverse_mass;
+                if total_inverse_mass == 0.0 { continue; }
+
+                // Push the entities apart in proportion to their inverse masses
+                let separation = contact.normal * (contact.depth / total_inverse_mass);
+                let (first_center, ..) = self.move_collider(
+                    &first_collider, 
+                    first_center, 
+                    -separation * first_inverse_mass
+                );
+                let (second_center, ..) = self.move_collider(
+                    &second_collider, 
+                    second_center, 
+                    separation * second_inverse_mass
+                );
+
+                // Only entities moving towards each other receive an impulse
+                let mut first_velocity = first_velocity;
+                let mut second_velocity = second_velocity;
+
+                let approach_speed = (second_velocity - first_velocity).dot(contact.normal);
+                if approach_speed < 0.0 {
+                    let impulse = -(1.0 + ENTITY_RESTITUTION) * approach_speed / total_inverse_mass;
+
+                    first_velocity -= contact.normal * (impulse * first_inverse_mass);
+                    second_velocity += contact.normal * (impulse * second_inverse_mass);
+                }
+
+                {
+                    let mut entity = first_handle.borrow_mut();
+                    entity.set_center(first_center);
+                    entity.set_velocity(first_velocity);
+                }
+
+                {
+                    let mut entity = second_handle.borrow_mut();
+                    entity.set_center(second_center);
+                    entity.set_velocity(second_velocity);
+                }
+            }
+        }
+    }
+}
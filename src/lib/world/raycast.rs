@@ -0,0 +1,161 @@
+use cgmath::{
+    Point3,
+    Vector3,
+    InnerSpace,
+    EuclideanSpace
+};
+
+use super::{
+    World,
+    TileShape,
+    CollisionMode
+};
+
+// Where a ray first struck a solid tile
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaycastHit {
+    pub tile: Point3<i16>,
+    pub point: Point3<f32>,
+    // Surface normal of the tile at the point that was struck
+    pub normal: Vector3<f32>,
+    pub distance: f32
+}
+
+// Distance along the ray to the point where it enters a convex shape, and the normal of the face it enters through
+// The shape is the box between `min` and `max`, cut by an optional plane whose normal faces out of the shape
+fn intersect_convex(
+    origin: Point3<f32>,
+    direction: Vector3<f32>,
+    min: Point3<f32>,
+    max: Point3<f32>,
+    plane: Option<(Point3<f32>, Vector3<f32>)>
+) -> Option<(f32, Vector3<f32>)> {
+    let (mut enter, mut exit) = (f32::NEG_INFINITY, f32::INFINITY);
+    let mut normal = Vector3::new(0.0, 0.0, 0.0);
+
+    for axis in 0..3 {
+        if direction[axis] == 0.0 {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] { return None; }
+            continue;
+        }
+
+        let near = (min[axis] - origin[axis]) / direction[axis];
+        let far = (max[axis] - origin[axis]) / direction[axis];
+        let (near, far) = if near < far { (near, far) } else { (far, near) };
+
+        if near > enter {
+            enter = near;
+            normal = Vector3::new(0.0, 0.0, 0.0);
+            normal[axis] = -direction[axis].signum();
+        }
+
+        exit = exit.min(far);
+    }
+
+    if let Some((point, plane_normal)) = plane {
+        let height = (origin - point).dot(plane_normal);
+        let rate = direction.dot(plane_normal);
+
+        if rate == 0.0 {
+            if height > 0.0 { return None; }
+        } else if rate < 0.0 {
+            let t = -height / rate;
+            if t > enter {
+                enter = t;
+                normal = plane_normal;
+            }
+        } else {
+            exit = exit.min(-height / rate);
+        }
+    }
+
+    if enter > exit || exit < 0.0 {
+        return None;
+    }
+
+    Some((enter.max(0.0), normal))
+}
+
+impl<'a> World<'a> {
+    // Casts a ray against the collision shapes of solid tiles, including one-way platforms
+    pub fn raycast(
+        &self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32
+    ) -> Option<RaycastHit> {
+        if direction.magnitude2() == 0.0 { return None; }
+        let direction = direction.normalize();
+
+        // Walk through the cells along the ray, nearest first
+        let mut cell = Point3::new(origin.x.round() as i16, origin.y.round() as i16, origin.z.round() as i16);
+        let mut step = [0i16; 3];
+        let mut next_boundary = [f32::INFINITY; 3];
+        let mut boundary_spacing = [f32::INFINITY; 3];
+
+        for axis in 0..3 {
+            if direction[axis] != 0.0 {
+                step[axis] = direction[axis].signum() as i16;
+
+                let boundary = cell[axis] as f32 + 0.5 * direction[axis].signum();
+                next_boundary[axis] = (boundary - origin[axis]) / direction[axis];
+                boundary_spacing[axis] = direction[axis].abs().recip();
+            }
+        }
+
+        let mut travelled = 0.0;
+        while travelled <= max_distance {
+            if let Some(hit) = self.raycast_tile(cell, origin, direction) {
+                return Some(hit).filter(|hit| hit.distance <= max_distance);
+            }
+
+            let axis = (0..3)
+                .min_by(|a, b| next_boundary[*a].total_cmp(&next_boundary[*b]))
+                .unwrap();
+
+            travelled = next_boundary[axis];
+            next_boundary[axis] += boundary_spacing[axis];
+            cell[axis] += step[axis];
+        }
+
+        None
+    }
+
+    fn raycast_tile(
+        &self,
+        cell: Point3<i16>,
+        origin: Point3<f32>,
+        direction: Vector3<f32>
+    ) -> Option<RaycastHit> {
+        let tile = self.tile_objects
+            .get(&cell)
+            .filter(|tile| tile.collision_mode() == CollisionMode::Solid)?;
+
+        let center = cell.cast::<f32>().unwrap();
+        let shape = tile.collision_shape();
+
+        let plane = match shape {
+            TileShape::Ramp { facing } => Some((center, TileShape::ramp_normal(facing))),
+            _ => None
+        };
+
+        shape.boxes()
+            .iter()
+            .filter_map(|&(min, max)| {
+                intersect_convex(origin, direction, center + min.to_vec(), center + max.to_vec(), plane)
+            } )
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(distance, normal)| RaycastHit {
+                tile: cell,
+                point: origin + direction * distance,
+                normal,
+                distance
+            } )
+    }
+
+    // Whether nothing solid lies on the straight line between two points
+    pub fn has_line_of_sight(&self, from: Point3<f32>, to: Point3<f32>) -> bool {
+        let offset = to - from;
+        self.raycast(from, offset, offset.magnitude()).is_none()
+    }
+}
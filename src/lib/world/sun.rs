@@ -0,0 +1,38 @@
+use cgmath::Vector3;
+
+use super::World;
+
+// Light that reaches the whole world from a single direction, and casts shadows
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sun {
+    // Direction that the light travels in
+    pub direction: Vector3<f32>,
+    // Intensity is stored in the alpha channel, as with other lights
+    pub color: [f32; 4],
+    // Fraction of the light that still reaches surfaces which face away from the sun or are in shadow
+    pub ambient: f32,
+    // Shadows are only drawn up to this distance from the camera
+    pub shadow_distance: f32
+}
+
+impl Default for Sun {
+    fn default() -> Self {
+        Self {
+            direction: Vector3::new(-0.4, -1.0, -0.3),
+            color: [1.0, 0.95, 0.85, 0.6],
+            ambient: 0.15,
+            shadow_distance: 60.0
+        }
+    }
+}
+
+impl<'a> World<'a> {
+    pub fn sun(&self) -> Option<Sun> {
+        self.sun
+    }
+
+    // The world is only lit by point lights when there is no sun
+    pub fn set_sun(&mut self, sun: Option<Sun>) {
+        self.sun = sun;
+    }
+}
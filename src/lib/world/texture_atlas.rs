@@ -0,0 +1,25 @@
+use crate::texture::TextureAtlas;
+
+use super::World;
+
+impl<'a> World<'a> {
+    pub fn texture_atlas(&self) -> Option<&TextureAtlas> {
+        self.texture_atlas.as_ref()
+    }
+
+    // Tiles and meshes take their texture coordinates from the atlas when they are built,
+    // so it is usually set before any of them are added
+    pub fn set_texture_atlas(&mut self, atlas: TextureAtlas) {
+        self.texture_atlas = Some(atlas);
+        self.texture_atlas_changed = true;
+    }
+
+    // The atlas, if it changed since the last time it was taken
+    pub(crate) fn take_texture_atlas_change(&mut self) -> Option<&TextureAtlas> {
+        if !std::mem::take(&mut self.texture_atlas_changed) {
+            return None;
+        }
+
+        self.texture_atlas.as_ref()
+    }
+}
@@ -1,9 +1,124 @@
-use cgmath::Point3;
+use std::rc::Rc;
 
-use super::drawable;
+use cgmath::{
+    Point3,
+    Vector3,
+    InnerSpace
+};
+
+use super::{
+    drawable,
+    collider::CollisionMode
+};
+
+// Describes how entities react when they touch a tile
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicalMaterial {
+    // Fraction of the entity's sliding velocity that is lost on each tick of contact
+    pub friction: f32,
+    // Fraction of the impact speed that is returned as a bounce
+    pub restitution: f32,
+    // Velocity of the surface itself, which entities in contact are dragged towards
+    pub conveyor: Option<Vector3<f32>>
+}
+
+impl Default for PhysicalMaterial {
+    fn default() -> Self {
+        Self { friction: 0.1, restitution: 0.0, conveyor: None }
+    }
+}
+
+// Horizontal direction that a ramp rises towards
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Facing {
+    PositiveX,
+    NegativeX,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl Facing {
+    pub fn direction(&self) -> Vector3<f32> {
+        match self {
+            Self::PositiveX => Vector3::unit_x(),
+            Self::NegativeX => -Vector3::unit_x(),
+            Self::PositiveZ => Vector3::unit_z(),
+            Self::NegativeZ => -Vector3::unit_z(),
+        }
+    }
+}
+
+// The part of a tile's cell that entities collide with
+// Coordinates are relative to the tile's position, so the full cell spans -0.5 to 0.5
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TileShape {
+    #[default]
+    Full,
+    // Bottom or top half of the cell
+    Slab { upper: bool },
+    // Slopes from the bottom of the cell up to the top, rising towards `facing`
+    Ramp { facing: Facing },
+    // Any number of boxes, given by their minimum and maximum corners
+    // The boxes are shared, so that tiles can hand out their shape without copying them
+    Boxes(Rc<[(Point3<f32>, Point3<f32>)]>),
+    // Thin surface at the top of the cell, which only blocks entities landing on it from above
+    OneWay,
+}
+
+impl TileShape {
+    // Thickness of the surface of a one-way platform
+    pub const ONE_WAY_THICKNESS: f32 = 0.1;
+
+    // The boxes making up the shape, in cell coordinates
+    // Ramps are not made of boxes, so they return their bounds
+    pub fn boxes(&self) -> &[(Point3<f32>, Point3<f32>)] {
+        const FULL: [(Point3<f32>, Point3<f32>); 1] = [
+            (Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.5, 0.5))
+        ];
+        const LOWER_SLAB: [(Point3<f32>, Point3<f32>); 1] = [
+            (Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.0, 0.5))
+        ];
+        const UPPER_SLAB: [(Point3<f32>, Point3<f32>); 1] = [
+            (Point3::new(-0.5, 0.0, -0.5), Point3::new(0.5, 0.5, 0.5))
+        ];
+        const ONE_WAY: [(Point3<f32>, Point3<f32>); 1] = [
+            (Point3::new(-0.5, 0.5 - TileShape::ONE_WAY_THICKNESS, -0.5), Point3::new(0.5, 0.5, 0.5))
+        ];
+
+        match self {
+            Self::Full | Self::Ramp { .. } => &FULL,
+            Self::Slab { upper: false } => &LOWER_SLAB,
+            Self::Slab { upper: true } => &UPPER_SLAB,
+            Self::Boxes(boxes) => boxes,
+            Self::OneWay => &ONE_WAY
+        }
+    }
+
+    // Normal of the sloped surface of a ramp
+    pub fn ramp_normal(facing: Facing) -> Vector3<f32> {
+        (Vector3::unit_y() - facing.direction()).normalize()
+    }
+}
 
 pub trait Tile: drawable::Drawable {
     fn position(&self) -> Point3<i16>;
     
     fn set_position(&mut self, position: Point3<i16>);
-}
\ No newline at end of file
+
+    fn physical_material(&self) -> PhysicalMaterial {
+        PhysicalMaterial::default()
+    }
+
+    // Solid tiles block entities, trigger tiles report entities that pass through them
+    fn collision_mode(&self) -> CollisionMode {
+        CollisionMode::Solid
+    }
+
+    // Should match the geometry built by the tile
+    fn collision_shape(&self) -> TileShape {
+        TileShape::Full
+    }
+
+    // Geometry in world space, which is only rebuilt when tiles change
+    fn build_object_data(&self) -> drawable::Triangles;
+}
@@ -0,0 +1,96 @@
+use cgmath::{
+    Point3,
+    EuclideanSpace,
+    MetricSpace
+};
+
+use std::ops::Range;
+
+use super::{
+    World,
+    Triangles,
+    MeshId
+};
+
+// A draw call in the transparent pass, which are made in order from the furthest from the eye to the nearest
+#[derive(Clone, Debug)]
+pub(crate) enum TransparentDraw {
+    // A run of tile triangles, as a range of the sorted transparent tile indices
+    Tiles(Range<u32>),
+    // A single instance of a mesh
+    Mesh(MeshId, u32)
+}
+
+impl<'a> World<'a> {
+    // Triangles of tiles that can be seen through, sorted from the furthest from the eye to the nearest,
+    // along with the draws that interleave them with the transparent mesh instances, which are already sorted
+    // They are drawn after everything else without writing depth, so the ones behind have to come first
+    pub(crate) fn build_transparent_draws(
+        &self,
+        eye: Point3<f32>,
+        meshes: &[(f32, MeshId, u32)]
+    ) -> (Vec<u32>, Vec<TransparentDraw>) {
+        // Degenerate triangles are left behind by tiles that were removed
+        let mut triangles = self.transparent_indices
+            .chunks_exact(3)
+            .filter(|triangle| triangle[0] != triangle[1] || triangle[1] != triangle[2])
+            .map(|triangle| {
+                let center = triangle
+                    .iter()
+                    .fold(Point3::origin(), |sum, index| sum + Point3::from(self.tile_vertices[*index as usize].position).to_vec())
+                    / 3.0;
+
+                (center.distance2(eye), triangle)
+            } )
+            .collect::<Vec<_>>();
+
+        triangles.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        let mut indices = Vec::with_capacity(triangles.len() * 3);
+        let mut draws = Vec::new();
+        let mut meshes = meshes.iter().peekable();
+        for (distance, triangle) in triangles {
+            // Meshes that are further away than the triangle go before it
+            while let Some((_, mesh, instance)) = meshes.next_if(|(mesh_distance, ..)| *mesh_distance >= distance) {
+                draws.push(TransparentDraw::Mesh(*mesh, *instance));
+            }
+
+            // Triangles in a row are drawn together
+            indices.extend_from_slice(triangle);
+            let end = indices.len() as u32;
+            match draws.last_mut() {
+                Some(TransparentDraw::Tiles(range)) => range.end = end,
+                _ => draws.push(TransparentDraw::Tiles(end - 3..end))
+            }
+        }
+
+        draws.extend(meshes.map(|(_, mesh, instance)| TransparentDraw::Mesh(*mesh, *instance)));
+
+        (indices, draws)
+    }
+}
+
+// Whether a color is drawn in the transparent pass
+pub(crate) fn is_transparent(color: [f32; 4]) -> bool {
+    color[3] < 1.0
+}
+
+// Splits the indices of a tile's triangles into those that are opaque and those that can be seen through
+pub(crate) fn split_transparent(triangles: &Triangles) -> (Vec<u32>, Vec<u32>) {
+    let mut opaque = Vec::with_capacity(triangles.indices.len());
+    let mut transparent = Vec::new();
+
+    for triangle in triangles.indices.chunks_exact(3) {
+        let see_through = triangle
+            .iter()
+            .any(|index| is_transparent(triangles.vertices[*index as usize].color));
+
+        if see_through {
+            transparent.extend_from_slice(triangle);
+        } else {
+            opaque.extend_from_slice(triangle);
+        }
+    }
+
+    (opaque, transparent)
+}
@@ -0,0 +1,84 @@
+use cgmath::Point3;
+
+use super::{
+    World,
+    CollisionMode,
+    EntityHandle,
+    TriggerEvent,
+    TriggerPhase,
+    TriggerSource
+};
+
+// Box-shaped region that reports the entities passing through it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TriggerVolume {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>
+}
+
+impl<'a> World<'a> {
+    pub fn add_trigger_volume(&mut self, tag: &'a str, min: Point3<f32>, max: Point3<f32>) {
+        self.trigger_volumes.insert(tag, TriggerVolume { min, max });
+    }
+
+    pub fn remove_trigger_volume(&mut self, tag: &str) -> Option<TriggerVolume> {
+        self.trigger_volumes.remove(tag)
+    }
+
+    pub fn get_trigger_volume(&self, tag: &str) -> Option<TriggerVolume> {
+        self.trigger_volumes.get(tag).copied()
+    }
+
+    // Trigger events from the last tick
+    // Events that are not drained are discarded when the next tick begins
+    pub fn drain_trigger_events(&mut self) -> Vec<TriggerEvent<'a>> {
+        std::mem::take(&mut self.trigger_events)
+    }
+
+    pub(crate) fn resolve_triggers(&mut self) {
+        self.trigger_events.clear();
+
+        let mut overlaps: Vec<(EntityHandle, TriggerSource<'a>)> = Vec::new();
+        for handle in self.entity_objects.iter() {
+            let entity = handle.borrow();
+            if entity.collision_mode() == CollisionMode::Ghost { continue; }
+
+            let (collider, center) = (entity.collider(), entity.center());
+
+            for tile in self.overlapping_tiles(&collider, center, CollisionMode::Trigger) {
+                overlaps.push((handle.clone(), TriggerSource::Tile(tile)));
+            }
+
+            for (tag, volume) in self.trigger_volumes.iter() {
+                if collider.intersects_box(center, volume.min, volume.max) {
+                    overlaps.push((handle.clone(), TriggerSource::Volume(tag)));
+                }
+            }
+        }
+
+        let previous_overlaps = std::mem::replace(&mut self.trigger_overlaps, overlaps);
+        let contains = |overlaps: &[(EntityHandle, TriggerSource<'a>)], entity: &EntityHandle, source| {
+            overlaps.iter().any(|(e, s)| e.ptr_eq(entity) && *s == source)
+        };
+
+        for (entity, source) in self.trigger_overlaps.iter() {
+            let phase = if contains(&previous_overlaps, entity, *source) {
+                TriggerPhase::Stay
+            } else {
+                TriggerPhase::Enter
+            };
+
+            self.trigger_events.push(TriggerEvent { entity: entity.clone(), source: *source, phase });
+        }
+
+        for (entity, source) in previous_overlaps.iter() {
+            if !contains(&self.trigger_overlaps, entity, *source) {
+                self.trigger_events.push(TriggerEvent { 
+                    entity: entity.clone(), 
+                    source: *source, 
+                    phase: TriggerPhase::Exit 
+                } );
+            }
+        }
+    }
+}
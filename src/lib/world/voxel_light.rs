@@ -0,0 +1,508 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::{BuildHasherDefault, Hasher}
+};
+
+use cgmath::{
+    Point3,
+    Vector3
+};
+
+use crate::vertex::Vertex;
+
+use super::World;
+
+// Brightest light level, which is also how many cells light travels through before it runs out
+pub const MAX_LIGHT_LEVEL: u8 = 15;
+
+// Padding around each tile that the light grid covers, so that light can spread out of the tiles' bounds
+const GRID_MARGIN: i32 = MAX_LIGHT_LEVEL as i32 + 1;
+
+// Width of the cubes of cells that the light grid is stored in
+const CHUNK_SIZE: i32 = 16;
+const CHUNK_VOLUME: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+// Past this many tile changes in a tick, the whole grid is rebuilt instead of the regions around each change
+const MAX_INCREMENTAL_CHANGES: usize = 64;
+
+const NEIGHBOURS: [[i32; 3]; 6] = [
+    [1, 0, 0], [-1, 0, 0],
+    [0, 1, 0], [0, -1, 0],
+    [0, 0, 1], [0, 0, -1]
+];
+
+// Light that floods from cell to cell instead of being worked out per fragment
+// Emissive tiles and the sky give off colored light levels, which drop by one with every cell they pass through,
+// and which can't pass through solid tiles. The result is baked into the vertices of tiles
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VoxelLighting {
+    // Color of the light that falls straight down into cells that are open to the sky, if any
+    pub skylight: Option<[f32; 3]>
+}
+
+// Chunk positions are looked up for almost every cell that light passes through,
+// so they are hashed by mixing their coordinates instead of with the slower default hasher
+#[derive(Default)]
+struct ChunkHasher(u64);
+
+impl Hasher for ChunkHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.write_u32(*byte as u32);
+        }
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.0 = (self.0.rotate_left(5) ^ value as u64).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+
+    fn write_i32(&mut self, value: i32) {
+        self.write_u32(value as u32);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// Light levels of a cube of cells, with a level for each color channel
+struct LightChunk {
+    levels: Vec<[u8; 3]>,
+    opaque: Vec<bool>
+}
+
+// Light level of each cell near a tile, kept in chunks so that tiles far apart don't need the space between them
+// Cells outside of the chunks are open, and dark unless they are above all of them
+pub(crate) struct LightGrid {
+    chunks: HashMap<Point3<i32>, LightChunk, BuildHasherDefault<ChunkHasher>>,
+    // Lowest and highest cells that the chunks cover
+    bottom: i32,
+    top: i32,
+    // Tile revision that the grid is up to date with
+    revision: usize
+}
+
+impl Default for LightGrid {
+    fn default() -> Self {
+        Self {
+            chunks: HashMap::default(),
+            bottom: i32::MAX,
+            top: i32::MIN,
+            revision: 0
+        }
+    }
+}
+
+impl LightGrid {
+    // The chunk that holds a cell, and the cell's index within it
+    fn locate(cell: Point3<i32>) -> (Point3<i32>, usize) {
+        let chunk = Point3::new(cell.x.div_euclid(CHUNK_SIZE), cell.y.div_euclid(CHUNK_SIZE), cell.z.div_euclid(CHUNK_SIZE));
+        let local = [cell.x, cell.y, cell.z].map(|value| value.rem_euclid(CHUNK_SIZE));
+
+        (chunk, (local[0] + CHUNK_SIZE * (local[1] + CHUNK_SIZE * local[2])) as usize)
+    }
+
+    // First and last cell of a chunk
+    fn chunk_bounds(chunk: Point3<i32>) -> (Point3<i32>, Point3<i32>) {
+        let min = Point3::new(chunk.x * CHUNK_SIZE, chunk.y * CHUNK_SIZE, chunk.z * CHUNK_SIZE);
+        (min, min + Vector3::new(CHUNK_SIZE - 1, CHUNK_SIZE - 1, CHUNK_SIZE - 1))
+    }
+
+    fn levels(&self, cell: Point3<i32>) -> Option<[u8; 3]> {
+        let (chunk, index) = Self::locate(cell);
+        self.chunks.get(&chunk).map(|chunk| chunk.levels[index])
+    }
+
+    fn set_levels(&mut self, cell: Point3<i32>, levels: [u8; 3]) {
+        let (chunk, index) = Self::locate(cell);
+        if let Some(chunk) = self.chunks.get_mut(&chunk) {
+            chunk.levels[index] = levels;
+        }
+    }
+
+    fn is_opaque(&self, cell: Point3<i32>) -> bool {
+        let (chunk, index) = Self::locate(cell);
+        self.chunks.get(&chunk).is_some_and(|chunk| chunk.opaque[index])
+    }
+
+    // Light levels of a cell that light can pass into, which are None for solid cells and cells outside of the chunks
+    fn open_levels_mut(&mut self, cell: Point3<i32>) -> Option<&mut [u8; 3]> {
+        let (chunk, index) = Self::locate(cell);
+        self.chunks
+            .get_mut(&chunk)
+            .filter(|chunk| !chunk.opaque[index])
+            .map(|chunk| &mut chunk.levels[index])
+    }
+
+    fn set_opaque(&mut self, cell: Point3<i32>, opaque: bool) {
+        let (chunk, index) = Self::locate(cell);
+        if let Some(chunk) = self.chunks.get_mut(&chunk) {
+            chunk.opaque[index] = opaque;
+        }
+    }
+
+    // Adds the chunks within the margin of a cell that don't exist yet, returning the box they cover, if any were added
+    fn add_chunks_around(&mut self, cell: Point3<i32>) -> Option<(Point3<i32>, Point3<i32>)> {
+        let margin = Vector3::new(GRID_MARGIN, GRID_MARGIN, GRID_MARGIN);
+        let (first, _) = Self::locate(cell - margin);
+        let (last, _) = Self::locate(cell + margin);
+
+        let mut added: Option<(Point3<i32>, Point3<i32>)> = None;
+        for z in first.z..=last.z {
+            for y in first.y..=last.y {
+                for x in first.x..=last.x {
+                    let chunk = Point3::new(x, y, z);
+                    if self.chunks.contains_key(&chunk) {
+                        continue;
+                    }
+
+                    self.chunks.insert(chunk, LightChunk {
+                        levels: vec![[0; 3]; CHUNK_VOLUME],
+                        opaque: vec![false; CHUNK_VOLUME]
+                    } );
+
+                    let (min, max) = Self::chunk_bounds(chunk);
+                    self.bottom = self.bottom.min(min.y);
+                    self.top = self.top.max(max.y);
+                    added = Some(match added {
+                        Some((added_min, added_max)) => (min_point(added_min, min), max_point(added_max, max)),
+                        None => (min, max)
+                    } );
+                }
+            }
+        }
+
+        added
+    }
+
+    // The chunks that overlap a box of cells
+    fn chunks_in(&self, min: Point3<i32>, max: Point3<i32>) -> Vec<Point3<i32>> {
+        self.chunks
+            .keys()
+            .filter(|chunk| {
+                let (chunk_min, chunk_max) = Self::chunk_bounds(**chunk);
+                chunk_min.x <= max.x && min.x <= chunk_max.x
+                    && chunk_min.y <= max.y && min.y <= chunk_max.y
+                    && chunk_min.z <= max.z && min.z <= chunk_max.z
+            } )
+            .copied()
+            .collect()
+    }
+}
+
+fn contains(min: Point3<i32>, max: Point3<i32>, cell: Point3<i32>) -> bool {
+    min.x <= cell.x && cell.x <= max.x
+        && min.y <= cell.y && cell.y <= max.y
+        && min.z <= cell.z && cell.z <= max.z
+}
+
+fn min_point(a: Point3<i32>, b: Point3<i32>) -> Point3<i32> {
+    Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z))
+}
+
+fn max_point(a: Point3<i32>, b: Point3<i32>) -> Point3<i32> {
+    Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z))
+}
+
+// The cells of a chunk that are inside of a box
+fn chunk_cells(chunk: Point3<i32>, min: Point3<i32>, max: Point3<i32>) -> impl Iterator<Item = Point3<i32>> {
+    let (chunk_min, chunk_max) = LightGrid::chunk_bounds(chunk);
+    let (min, max) = (max_point(min, chunk_min), min_point(max, chunk_max));
+
+    (min.z..=max.z).flat_map(move |z| {
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Point3::new(x, y, z)))
+    } )
+}
+
+// Light levels of a color, as given by `Drawable::light` or the skylight
+fn light_levels(color: [f32; 3], intensity: f32) -> [u8; 3] {
+    color.map(|channel| ((channel * intensity).clamp(0.0, 1.0) * MAX_LIGHT_LEVEL as f32).round() as u8)
+}
+
+impl<'a> World<'a> {
+    pub fn voxel_lighting(&self) -> Option<VoxelLighting> {
+        self.voxel_lighting
+    }
+
+    // Emissive tiles stop being point lights while voxel lighting is enabled, though entities and particles still are
+    pub fn set_voxel_lighting(&mut self, lighting: Option<VoxelLighting>) {
+        self.voxel_lighting = lighting;
+
+        self.rebuild_light_grid();
+        self.relight_tile_vertices(None);
+    }
+
+    // Light levels of the cell at a position, which are all zero while voxel lighting is disabled
+    pub fn light_level(&self, position: Point3<i16>) -> [u8; 3] {
+        self.cell_light_level(position.cast::<i32>().unwrap())
+    }
+
+    fn cell_light_level(&self, cell: Point3<i32>) -> [u8; 3] {
+        let lighting = match self.voxel_lighting {
+            Some(lighting) => lighting,
+            None => return [0; 3]
+        };
+
+        match self.light_grid.levels(cell) {
+            Some(levels) => levels,
+            // Nothing is above the chunks, so the sky reaches everything there
+            None if cell.y > self.light_grid.top => lighting.skylight
+                .map(|sky| light_levels(sky, 1.0))
+                .unwrap_or_default(),
+            None => [0; 3]
+        }
+    }
+
+    // Brings the light grid up to date with the tiles that changed since the last tick
+    pub(crate) fn resolve_voxel_light(&mut self) {
+        if self.voxel_lighting.is_none() || self.light_grid.revision == self.tile_revision() {
+            return;
+        }
+
+        // Changes that the world no longer remembers could be anywhere, so they need a rebuild too
+        let changes = self
+            .tile_changes_since(self.light_grid.revision)
+            .map(|changes| changes.map(|position| position.cast::<i32>().unwrap()).collect::<Vec<_>>())
+            .filter(|changes| changes.len() <= MAX_INCREMENTAL_CHANGES);
+
+        let changes = match changes {
+            Some(changes) => changes,
+            None => {
+                self.rebuild_light_grid();
+                self.relight_tile_vertices(None);
+                return;
+            }
+        };
+
+        // A change can only affect cells that light could reach by passing through it,
+        // along with everything below it that the sky might now reach
+        let range = Vector3::new(MAX_LIGHT_LEVEL as i32, MAX_LIGHT_LEVEL as i32, MAX_LIGHT_LEVEL as i32);
+        let mut min = Point3::new(i32::MAX, i32::MAX, i32::MAX);
+        let mut max = Point3::new(i32::MIN, i32::MIN, i32::MIN);
+        for cell in changes.iter() {
+            min = min_point(min, cell - range);
+            max = max_point(max, cell + range);
+
+            // Chunks that are added around a change start out dark, so all of their cells are lit
+            if let Some((added_min, added_max)) = self.light_grid.add_chunks_around(*cell) {
+                min = min_point(min, added_min);
+                max = max_point(max, added_max);
+            }
+        }
+        min.y = self.light_grid.bottom;
+
+        for cell in changes {
+            let solid = self.is_solid(cell.cast::<i16>().unwrap());
+            self.light_grid.set_opaque(cell, solid);
+        }
+
+        self.light_region(min, max);
+        self.light_grid.revision = self.tile_revision();
+
+        self.relight_tile_vertices(Some((min, max)));
+    }
+
+    fn rebuild_light_grid(&mut self) {
+        self.light_grid = LightGrid { revision: self.tile_revision(), ..Default::default() };
+        if self.voxel_lighting.is_none() || self.tile_objects.is_empty() {
+            return;
+        }
+
+        let cells = self.tile_objects
+            .keys()
+            .map(|position| (position.cast::<i32>().unwrap(), self.is_solid(*position)))
+            .collect::<Vec<_>>();
+
+        let mut min = Point3::new(i32::MAX, i32::MAX, i32::MAX);
+        let mut max = Point3::new(i32::MIN, i32::MIN, i32::MIN);
+        for (cell, solid) in cells {
+            if let Some((added_min, added_max)) = self.light_grid.add_chunks_around(cell) {
+                min = min_point(min, added_min);
+                max = max_point(max, added_max);
+            }
+
+            self.light_grid.set_opaque(cell, solid);
+        }
+
+        self.light_region(min, max);
+    }
+
+    // Works out the light of every cell in a box of the grid from scratch
+    // Cells just outside of the box keep their light, and shine into it
+    // Only the chunks that overlap the box are visited, so the box can be much larger than the tiles in it
+    fn light_region(&mut self, min: Point3<i32>, max: Point3<i32>) {
+        let mut queue = VecDeque::new();
+
+        let one = Vector3::new(1, 1, 1);
+        let chunks = self.light_grid.chunks_in(min - one, max + one);
+
+        for chunk in chunks.iter() {
+            for cell in chunk_cells(*chunk, min, max) {
+                self.light_grid.set_levels(cell, [0; 3]);
+            }
+        }
+
+        // Skylight falls without getting any dimmer, until it reaches a solid tile
+        // Cells between chunks are open, so each column only has to be followed through the chunks in it
+        if let Some(sky) = self.voxel_lighting.and_then(|lighting| lighting.skylight) {
+            let sky = light_levels(sky, 1.0);
+
+            let mut columns = HashMap::<(i32, i32), Vec<i32>>::new();
+            let column_min = Point3::new(min.x, self.light_grid.bottom, min.z);
+            let column_max = Point3::new(max.x, self.light_grid.top, max.z);
+            for chunk in self.light_grid.chunks_in(column_min, column_max) {
+                columns.entry((chunk.x, chunk.z)).or_default().push(chunk.y);
+            }
+
+            for ((chunk_x, chunk_z), mut heights) in columns {
+                heights.sort_unstable_by(|a, b| b.cmp(a));
+
+                let (chunk_min, chunk_max) = LightGrid::chunk_bounds(Point3::new(chunk_x, 0, chunk_z));
+                for z in chunk_min.z.max(min.z)..=chunk_max.z.min(max.z) {
+                    for x in chunk_min.x.max(min.x)..=chunk_max.x.min(max.x) {
+                        'column: for height in heights.iter() {
+                            let (bottom, top) = (height * CHUNK_SIZE, height * CHUNK_SIZE + CHUNK_SIZE - 1);
+                            for y in (bottom.max(min.y)..=top).rev() {
+                                let cell = Point3::new(x, y, z);
+                                if self.light_grid.is_opaque(cell) {
+                                    break 'column;
+                                }
+
+                                if y <= max.y {
+                                    self.light_grid.set_levels(cell, sky);
+                                    queue.push_back(cell);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Emissive tiles light their own cell, even though it's usually solid
+        let sources = self.tile_objects
+            .iter()
+            .filter_map(|(position, tile)| Some((position.cast::<i32>().unwrap(), tile.light()?)))
+            .filter(|(cell, _)| contains(min, max, *cell))
+            .collect::<Vec<_>>();
+
+        for (cell, light) in sources {
+            if let Some(current) = self.light_grid.levels(cell) {
+                let levels = light_levels([light[0], light[1], light[2]], light[3]);
+                self.light_grid.set_levels(cell, [0, 1, 2].map(|channel| current[channel].max(levels[channel])));
+                queue.push_back(cell);
+            }
+        }
+
+        // The faces of the box are lit by the cells next to them
+        for chunk in chunks.iter() {
+            for cell in chunk_cells(*chunk, min - one, max + one) {
+                if !contains(min, max, cell) {
+                    queue.push_back(cell);
+                }
+            }
+        }
+
+        while let Some(cell) = queue.pop_front() {
+            let levels = self.light_grid.levels(cell).unwrap_or_default();
+            if levels.iter().all(|level| *level <= 1) {
+                continue;
+            }
+
+            let dimmed = levels.map(|level| level.saturating_sub(1));
+            for offset in NEIGHBOURS {
+                let neighbour = Point3::new(cell.x + offset[0], cell.y + offset[1], cell.z + offset[2]);
+                if !contains(min, max, neighbour) {
+                    continue;
+                }
+
+                let current = match self.light_grid.open_levels_mut(neighbour) {
+                    Some(current) => current,
+                    None => continue
+                };
+
+                if (0..3).any(|channel| dimmed[channel] > current[channel]) {
+                    *current = [0, 1, 2].map(|channel| current[channel].max(dimmed[channel]));
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+    }
+
+    // Light at a point, blended between the centres of the cells around it
+    // Solid cells without light of their own are left out, so that walls don't darken the corners next to them
+    pub(crate) fn sample_voxel_light(&self, point: Point3<f32>) -> [f32; 3] {
+        if self.voxel_lighting.is_none() {
+            return [0.0; 3];
+        }
+
+        let base = Point3::new(point.x.floor(), point.y.floor(), point.z.floor());
+        let fraction = point - base;
+        let base = base.cast::<i32>().unwrap();
+
+        let mut total = [0.0; 3];
+        let mut total_weight = 0.0;
+        for corner in 0..8 {
+            let offset = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let weight = (0..3)
+                .map(|axis| if offset[axis] == 1 { fraction[axis] } else { 1.0 - fraction[axis] })
+                .product::<f32>();
+
+            let cell = Point3::new(base.x + offset[0], base.y + offset[1], base.z + offset[2]);
+            let levels = self.cell_light_level(cell);
+            let opaque = self.light_grid.is_opaque(cell);
+            if weight <= 0.0 || (opaque && levels == [0; 3]) {
+                continue;
+            }
+
+            for channel in 0..3 {
+                total[channel] += weight * levels[channel] as f32;
+            }
+            total_weight += weight;
+        }
+
+        if total_weight <= 0.0 {
+            return [0.0; 3];
+        }
+
+        total.map(|level| level / total_weight / MAX_LIGHT_LEVEL as f32)
+    }
+
+    // Light is sampled half a cell in front of each vertex, in the cells that the surface faces
+    pub(crate) fn light_vertices(&self, vertices: &mut [Vertex]) {
+        for vertex in vertices.iter_mut() {
+            vertex.light = self.sample_voxel_light(vertex_sample_point(vertex));
+        }
+    }
+
+    // Bakes the light grid into the tile vertices that sample it within a box, or into all of them
+    fn relight_tile_vertices(&mut self, region: Option<(Point3<i32>, Point3<i32>)>) {
+        let mut vertices = std::mem::take(&mut self.tile_vertices);
+
+        let mut first_changed = None;
+        for (index, vertex) in vertices.iter_mut().enumerate() {
+            let point = vertex_sample_point(vertex);
+            let inside = region.is_none_or(|(min, max)| {
+                let cell = Point3::new(point.x.floor() as i32, point.y.floor() as i32, point.z.floor() as i32);
+                contains(min - Vector3::new(1, 1, 1), max, cell)
+            } );
+
+            if inside {
+                let light = self.sample_voxel_light(point);
+                if light != vertex.light {
+                    vertex.light = light;
+                    first_changed.get_or_insert(index);
+                }
+            }
+        }
+
+        self.tile_vertices = vertices;
+        if let Some(first) = first_changed {
+            self.mark_tile_geometry_changed(first, self.tile_indices.len());
+        }
+    }
+}
+
+fn vertex_sample_point(vertex: &Vertex) -> Point3<f32> {
+    Point3::from(vertex.position) + Vector3::from(vertex.normal) * 0.5
+}